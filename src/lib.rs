@@ -16,20 +16,94 @@
 
 #![no_std]
 
-use core::cmp::min;
+use core::cmp::{max, min};
 use core::fmt;
 use core::str::from_utf8;
 
+/// The reason a write into a `WriteTo` failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WriteError {
+    /// The output did not fit into the buffer.
+    Overflow,
+    /// A formatting trait implementation returned an error.
+    Format,
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            WriteError::Overflow => "buffer overflow",
+            WriteError::Format => "formatting error",
+        })
+    }
+}
+
+impl From<WriteError> for fmt::Error {
+    fn from(_: WriteError) -> Self {
+        fmt::Error
+    }
+}
+
 /// A struct representing a writer that appends formatted data to a byte buffer.
 pub struct WriteTo<'a> {
     buf: &'a mut [u8],
     len: usize,
+    high_water: usize,
+    last_required: usize,
+    panic_on_overflow: bool,
+    segments: usize,
+    last_error: Option<WriteError>,
+    measuring: bool,
 }
 
 impl<'a> WriteTo<'a> {
     /// Constructs a new `WriteTo` instance wrapping the provided byte buffer.
     pub fn new(buf: &'a mut [u8]) -> Self {
-        WriteTo { buf, len: 0 }
+        WriteTo {
+            buf,
+            len: 0,
+            high_water: 0,
+            last_required: 0,
+            panic_on_overflow: false,
+            segments: 0,
+            last_error: None,
+            measuring: false,
+        }
+    }
+
+    /// Constructs a `WriteTo` that panics instead of returning an error on overflow.
+    ///
+    /// Meant for debugging mis-sized buffers: the panic message reports the
+    /// needed and the available number of bytes at the overflowing write.
+    /// Every write that runs out of space panics, including the atomic and
+    /// framed ones; only `write_if_fits`, which checks the space up front,
+    /// still just returns `false`.
+    pub fn panicking(buf: &'a mut [u8]) -> Self {
+        WriteTo {
+            panic_on_overflow: true,
+            ..WriteTo::new(buf)
+        }
+    }
+
+    /// Constructs a new `WriteTo`, failing if `buf` is shorter than `min_capacity`.
+    pub fn new_checked(buf: &'a mut [u8], min_capacity: usize) -> Result<Self, fmt::Error> {
+        if buf.len() < min_capacity {
+            return Err(fmt::Error);
+        }
+
+        Ok(WriteTo::new(buf))
+    }
+
+    /// Constructs a new `WriteTo` whose content starts with the UTF-8 byte order mark.
+    ///
+    /// The three BOM bytes (`EF BB BF`) count towards the length but not towards
+    /// `segments`, and the string returned by `as_str` starts with `U+FEFF`. If
+    /// `buf` cannot hold the BOM the writer starts out overflowed.
+    pub fn with_bom(buf: &'a mut [u8]) -> Self {
+        let mut w = WriteTo::new(buf);
+        let _ = w.write_bytes(b"\xef\xbb\xbf");
+        w
     }
 
     /// Converts the written portion of the buffer into a string slice, if possible.
@@ -41,6 +115,15 @@ impl<'a> WriteTo<'a> {
         }
     }
 
+    /// Consumes the writer, returning the string slice (as `as_str` does), the
+    /// number of bytes actually stored in the buffer and the buffer capacity.
+    pub fn finish(self) -> (Option<&'a str>, usize, usize) {
+        let len = self.written_len();
+        let cap = self.buf.len();
+
+        (self.as_str(), len, cap)
+    }
+
     /// Get the number of bytes written to buffer, unless there where errors.
     pub fn len(&self) -> Option<usize> {
         if self.len <= self.buf.len() {
@@ -58,83 +141,3859 @@ impl<'a> WriteTo<'a> {
             None
         }
     }
-}
 
-impl<'a> fmt::Write for WriteTo<'a> {
-    /// Writes a string slice into the buffer, updating the length accordingly.
-    fn write_str(&mut self, s: &str) -> fmt::Result {
+    /// Get the number of bytes actually stored in the buffer, which is the
+    /// whole buffer if the writer overflowed.
+    pub fn written_len(&self) -> usize {
+        min(self.len, self.buf.len())
+    }
+
+    /// Returns true if no bytes are stored in the buffer, whether or not there were errors.
+    pub fn is_written_empty(&self) -> bool {
+        self.written_len() == 0
+    }
+
+    /// Returns how full the buffer is, in percent (0 to 100).
+    ///
+    /// An overflowed writer and a zero-sized buffer report 100.
+    pub fn fill_percent(&self) -> u8 {
+        let cap = self.buf.len();
+        if self.len >= cap {
+            100
+        } else {
+            (self.len as u64 * 100 / cap as u64) as u8
+        }
+    }
+
+    /// Returns true if the content fills the buffer exactly, without overflowing.
+    pub fn at_capacity(&self) -> bool {
+        self.len == self.buf.len()
+    }
+
+    /// Returns true if `n` more bytes fit into the buffer.
+    pub fn can_fit(&self, n: usize) -> bool {
+        self.len <= self.buf.len() && n <= self.buf.len() - self.len
+    }
+
+    /// Returns the number of bytes still free in the buffer, 0 if the writer overflowed.
+    pub fn remaining(&self) -> usize {
+        self.buf.len().saturating_sub(self.len)
+    }
+
+    /// Clears the written content so the buffer can be reused.
+    pub fn reset(&mut self) {
+        self.high_water = self.high_water();
+        self.len = 0;
+        self.segments = 0;
+        self.last_error = None;
+    }
+
+    /// Points the writer at a new buffer and clears the written content, as
+    /// `reset` does. Settings such as the panicking mode and the high-water
+    /// mark are kept.
+    ///
+    /// The new buffer must live at least as long as the writer's lifetime `'a`,
+    /// just like the one passed at construction. The previous buffer is released
+    /// by the writer but stays borrowed for `'a` at the call site.
+    pub fn set_buf(&mut self, buf: &'a mut [u8]) {
+        self.reset();
+        self.buf = buf;
+    }
+
+    /// Returns the largest length, including the bytes required by overflowing
+    /// writes, seen since construction. It is kept across `reset` calls.
+    pub fn high_water(&self) -> usize {
+        max(self.high_water, self.len)
+    }
+
+    /// Returns the number of bytes the most recent `write!` (`write_fmt`) call
+    /// needed in total, whether it fit or not. Useful to size a retry.
+    ///
+    /// Once a `write!` overflows, the rest of its output is only counted, not
+    /// stored, so the arguments are still formatted just once.
+    pub fn last_required(&self) -> usize {
+        self.last_required
+    }
+
+    /// Returns how many times `write_str` was called since construction or the
+    /// last `reset`. Each literal piece and each argument of a format string
+    /// usually takes at least one call.
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    /// Returns the reason of the most recent failed write since construction
+    /// or the last `reset`, so a series of writes can be checked once at the end.
+    pub fn last_error(&self) -> Option<WriteError> {
+        self.last_error
+    }
+
+    /// Checks that the bytes stored in the buffer are valid UTF-8.
+    ///
+    /// On failure returns the offset of the first invalid byte, for example
+    /// where an overflowing write cut a multi-byte character in half.
+    pub fn validate(&self) -> Result<&str, usize> {
+        let len = self.written_len();
+        from_utf8(&self.buf[..len]).map_err(|e| e.valid_up_to())
+    }
+
+    /// Returns an iterator over the written content in pieces of at most `max` bytes.
+    ///
+    /// Pieces are only split on character boundaries, so each one is valid
+    /// UTF-8 on its own. A character wider than `max` is yielded whole. Only the
+    /// valid UTF-8 prefix of the stored bytes is iterated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0.
+    pub fn chunks_utf8(&self, max: usize) -> impl Iterator<Item = &str> {
+        assert!(max != 0, "chunk size must be non-zero");
+
+        let len = self.written_len();
+        let mut rest = utf8_prefix(&self.buf[..len]);
+        core::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+
+            let mut end = min(max, rest.len());
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            if end == 0 {
+                end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+            }
+
+            let (chunk, tail) = rest.split_at(end);
+            rest = tail;
+            Some(chunk)
+        })
+    }
+
+    /// Writes `args` as a frame prefixed with its little-endian `u16` byte length.
+    ///
+    /// Two bytes are reserved for the length, the payload is formatted after
+    /// them and the length is filled in afterwards. If anything does not fit,
+    /// the writer is rolled back to its state before the call.
+    pub fn write_framed(&mut self, args: fmt::Arguments) -> Result<(), fmt::Error> {
+        self.transaction(|w| {
+            let start = w.len;
+            if w.remaining() < 2 {
+                return w.overflow(start.saturating_add(2));
+            }
+            w.len += 2;
+
+            fmt::write(w, args)?;
+            let payload = w.len - start - 2;
+            if payload > u16::MAX as usize {
+                return w.fail(WriteError::Format);
+            }
+
+            w.buf[start..start + 2].copy_from_slice(&(payload as u16).to_le_bytes());
+            Ok(())
+        })
+    }
+
+    /// Writes `prefix`, the formatted `args` and `suffix` as a single operation.
+    ///
+    /// If any part does not fit, the writer is rolled back to its state before
+    /// the call, so no partially wrapped output is left behind.
+    pub fn write_wrapped(
+        &mut self,
+        prefix: &str,
+        args: fmt::Arguments,
+        suffix: &str,
+    ) -> Result<(), fmt::Error> {
+        use fmt::Write;
+        self.transaction(|w| {
+            w.write_str(prefix)?;
+            fmt::write(w, args)?;
+            w.write_str(suffix)
+        })
+    }
+
+    /// Writes `items` as a bracketed, comma separated list, e.g. `[1, 2, 3]`.
+    pub fn write_debug_list<T: fmt::Display>(&mut self, items: &[T]) -> Result<(), fmt::Error> {
+        use fmt::Write;
+        self.write_str("[")?;
+        write_join(self, items, ", ")?;
+        self.write_str("]")
+    }
+
+    /// Runs `f` against a writer limited to at most `max` more bytes.
+    ///
+    /// Whatever fits into the scope is kept (cut back to a character
+    /// boundary) and the full remaining capacity is available again
+    /// afterwards. An overflow inside the scope does not affect this writer,
+    /// unless it was built with `panicking`, in which case the scope panics too.
+    pub fn scoped<R>(&mut self, max: usize, f: impl FnOnce(&mut WriteTo) -> R) -> R {
+        let start = self.written_len();
+        let end = start + min(max, self.buf.len() - start);
+
+        let mut scope = WriteTo {
+            panic_on_overflow: self.panic_on_overflow,
+            ..WriteTo::new(&mut self.buf[start..end])
+        };
+        let res = f(&mut scope);
+        let len = scope.written_len();
+        let kept = utf8_prefix(&scope.buf[..len]).len();
+
+        self.len += kept;
+        self.high_water = self.high_water.max(self.len);
+        res
+    }
+
+    /// Returns the written content together with the unused tail of the buffer.
+    ///
+    /// The content is cut back to its valid UTF-8 prefix. The tail is empty if
+    /// the writer overflowed.
+    pub fn split_written(&mut self) -> (&str, &mut [u8]) {
+        let len = self.written_len();
+        let (head, tail) = self.buf.split_at_mut(len);
+
+        (utf8_prefix(head), tail)
+    }
+
+    /// Writes `data` as a Rust byte string literal, e.g. `b"\x00A"`.
+    ///
+    /// Printable ASCII is kept as is, `"` and `\` are backslash escaped and
+    /// every other byte is written as `\xNN`.
+    pub fn write_byte_literal(&mut self, data: &[u8]) -> Result<(), fmt::Error> {
+        use fmt::Write;
+        self.write_str("b\"")?;
+        for &b in data {
+            match b {
+                b'"' => self.write_str("\\\"")?,
+                b'\\' => self.write_str("\\\\")?,
+                0x20..=0x7e => self.write_char(char::from(b))?,
+                _ => self.write_args(format_args!("\\x{:02x}", b))?,
+            }
+        }
+        self.write_str("\"")
+    }
+
+    /// Replaces every `from` byte in the written content with `to`, in place.
+    ///
+    /// Both bytes must be ASCII so the content stays valid UTF-8; otherwise the
+    /// call does nothing.
+    pub fn replace_byte(&mut self, from: u8, to: u8) {
+        if !from.is_ascii() || !to.is_ascii() {
+            return;
+        }
+
+        let len = self.written_len();
+        for b in &mut self.buf[..len] {
+            if *b == from {
+                *b = to;
+            }
+        }
+    }
+
+    /// Drops trailing ASCII whitespace from the written content.
+    ///
+    /// Does nothing if the writer overflowed.
+    pub fn trim_end(&mut self) {
         if self.len > self.buf.len() {
-            return Err(fmt::Error);
+            return;
+        }
+
+        while self.len > 0 && self.buf[self.len - 1].is_ascii_whitespace() {
+            self.len -= 1;
+        }
+    }
+
+    /// Writes `d` compactly, e.g. `1m30s`, `250ms`, `15us` or `500ns`.
+    ///
+    /// Durations of a second or more are split into hours, minutes and seconds,
+    /// leaving out zero parts. Below a minute the whole milliseconds follow the
+    /// seconds, e.g. `1s500ms`; from a minute on any fraction of a second is
+    /// dropped. Shorter durations are written as whole milliseconds, or
+    /// microseconds below a millisecond and nanoseconds below a microsecond.
+    /// Only integer arithmetic is used.
+    pub fn write_duration(&mut self, d: core::time::Duration) -> Result<(), fmt::Error> {
+        use fmt::Write;
+        let secs = d.as_secs();
+        if secs == 0 {
+            let ms = u64::from(d.subsec_millis());
+            return match (ms, d.subsec_micros(), d.subsec_nanos()) {
+                (0, 0, 0) => self.write_str("0s"),
+                (0, 0, ns) => write_u64(self, u64::from(ns)).and_then(|_| self.write_str("ns")),
+                (0, us, _) => write_u64(self, u64::from(us)).and_then(|_| self.write_str("us")),
+                _ => write_u64(self, ms).and_then(|_| self.write_str("ms")),
+            };
+        }
+
+        let (h, m, s) = split_hms(secs);
+        for &(value, unit) in &[(h, "h"), (m, "m"), (s, "s")] {
+            if value != 0 {
+                write_u64(self, value)?;
+                self.write_str(unit)?;
+            }
+        }
+
+        let ms = d.subsec_millis();
+        if secs < 60 && ms != 0 {
+            write_u64(self, u64::from(ms))?;
+            self.write_str("ms")?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes `data` as hex, inserting `sep` after every `group` bytes.
+    ///
+    /// A `group` of zero writes all digits without separators. For example
+    /// `[0xde, 0xad, 0xbe, 0xef]` with `group = 2`, `sep = ' '` and `upper`
+    /// set gives `DEAD BEEF`.
+    pub fn write_hex_grouped(
+        &mut self,
+        data: &[u8],
+        group: usize,
+        sep: char,
+        upper: bool,
+    ) -> Result<(), fmt::Error> {
+        use fmt::Write;
+        for (i, b) in data.iter().enumerate() {
+            if group != 0 && i != 0 && i % group == 0 {
+                self.write_char(sep)?;
+            }
+            if upper {
+                self.write_args(format_args!("{:02X}", b))?;
+            } else {
+                self.write_args(format_args!("{:02x}", b))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes `s` as a quoted C string literal.
+    ///
+    /// `\n`, `\r`, `\t`, `"` and `\` get their backslash escapes. Every other
+    /// byte outside printable ASCII, including each byte of a multi-byte
+    /// character, is written as a three digit octal escape such as `\001`.
+    pub fn write_c_string(&mut self, s: &str) -> Result<(), fmt::Error> {
+        use fmt::Write;
+        self.write_str("\"")?;
+        for b in s.bytes() {
+            match b {
+                b'\n' => self.write_str("\\n")?,
+                b'\r' => self.write_str("\\r")?,
+                b'\t' => self.write_str("\\t")?,
+                b'"' => self.write_str("\\\"")?,
+                b'\\' => self.write_str("\\\\")?,
+                0x20..=0x7e => self.write_char(char::from(b))?,
+                _ => self.write_args(format_args!("\\{:03o}", b))?,
+            }
+        }
+        self.write_str("\"")
+    }
+
+    /// Appends `s` only if it fits entirely.
+    ///
+    /// Unlike `write_str`, nothing is copied on failure, so both the buffer and
+    /// the length stay unchanged.
+    pub fn write_str_atomic(&mut self, s: &str) -> Result<(), fmt::Error> {
+        if !self.can_fit(s.len()) {
+            return self.overflow(self.len.saturating_add(s.len()));
+        }
+
+        fmt::Write::write_str(self, s)
+    }
+
+    /// Writes `table[index]`, or `fallback` if `index` is out of range.
+    pub fn write_from_table(
+        &mut self,
+        index: usize,
+        table: &[&str],
+        fallback: &str,
+    ) -> Result<(), fmt::Error> {
+        fmt::Write::write_str(self, table.get(index).copied().unwrap_or(fallback))
+    }
+
+    /// Returns the written bytes and the unused tail of the buffer, both mutable.
+    ///
+    /// The written part can be patched in place and the tail filled directly;
+    /// call `advance` afterwards to include appended bytes in the content. The
+    /// tail is empty if the writer overflowed.
+    pub fn split_written_mut(&mut self) -> (&mut [u8], &mut [u8]) {
+        let len = self.written_len();
+        self.buf.split_at_mut(len)
+    }
+
+    /// Marks `n` more bytes, filled in directly through `split_written_mut`, as written.
+    ///
+    /// Advancing past the end of the buffer leaves the writer overflowed.
+    pub fn advance(&mut self, n: usize) {
+        self.len = self.len.saturating_add(n);
+        self.high_water = max(self.high_water, self.len);
+    }
+
+    /// Writes `data` escaped byte by byte like `core::ascii::escape_default`.
+    ///
+    /// `\t`, `\r`, `\n`, `'`, `"` and `\` get their backslash escapes, other
+    /// printable ASCII is copied and every remaining byte is written as `\xNN`.
+    pub fn write_escaped_bytes(&mut self, data: &[u8]) -> Result<(), fmt::Error> {
+        use fmt::Write;
+        for &b in data {
+            match b {
+                b'\t' => self.write_str("\\t")?,
+                b'\r' => self.write_str("\\r")?,
+                b'\n' => self.write_str("\\n")?,
+                b'\'' => self.write_str("\\'")?,
+                b'"' => self.write_str("\\\"")?,
+                b'\\' => self.write_str("\\\\")?,
+                0x20..=0x7e => self.write_char(char::from(b))?,
+                _ => self.write_args(format_args!("\\x{:02x}", b))?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Appends the written content of `other`.
+    ///
+    /// Fails without copying anything if `other` overflowed, recording
+    /// `WriteError::Overflow`.
+    pub fn append_from(&mut self, other: &WriteTo) -> Result<(), fmt::Error> {
+        if other.len > other.buf.len() {
+            return self.fail(WriteError::Overflow);
+        }
+
+        self.write_bytes(&other.buf[..other.len])
+    }
+
+    /// Copies `raw_s` into the buffer, updating the length accordingly.
+    ///
+    /// While `write_fmt` is measuring, bytes past the end of the buffer are
+    /// counted without failing, and the overflow is reported by `write_fmt`.
+    fn write_bytes(&mut self, raw_s: &[u8]) -> fmt::Result {
+        if self.len > self.buf.len() {
+            if !self.measuring {
+                return self.overflow(self.len);
+            }
+            self.len = self.len.saturating_add(raw_s.len());
+            self.high_water = max(self.high_water, self.len);
+            return Ok(());
         }
 
         let rem = &mut self.buf[self.len..];
-        let raw_s = s.as_bytes();
         let num = min(raw_s.len(), rem.len());
 
         rem[..num].copy_from_slice(&raw_s[..num]);
         self.len += raw_s.len();
+        self.high_water = max(self.high_water, self.len);
 
-        if num < raw_s.len() {
-            Err(fmt::Error)
+        if num < raw_s.len() && !self.measuring {
+            self.overflow(self.len)
         } else {
             Ok(())
         }
     }
+
+    /// Writes `value` in decimal with an explicit sign.
+    ///
+    /// Negative values always get `-`; non-negative ones get `+` when
+    /// `force_plus` is set. Only integer arithmetic is used.
+    pub fn write_signed(&mut self, value: i64, force_plus: bool) -> Result<(), fmt::Error> {
+        use fmt::Write;
+        if value < 0 {
+            self.write_str("-")?;
+        } else if force_plus {
+            self.write_str("+")?;
+        }
+        write_u64(self, value.unsigned_abs())
+    }
+
+    /// Writes `arg` only if its whole output fits into the remaining space.
+    ///
+    /// The output length is measured in a first pass, so `arg` is formatted
+    /// twice when it fits. Returns whether the output was written; if not, the
+    /// writer is left unchanged and does not become overflowed.
+    pub fn write_if_fits(&mut self, arg: fmt::Arguments) -> bool {
+        match measure(arg) {
+            Ok(n) if self.can_fit(n) => self.transaction(|w| fmt::write(w, arg)).is_ok(),
+            Ok(_) => false,
+            Err(_) => {
+                let _ = self.fail(WriteError::Format);
+                false
+            }
+        }
+    }
+
+    /// Writes `pairs` as `key<kv_sep>value` entries separated by `pair_sep`.
+    ///
+    /// For example `[("a", 1), ("b", 2)]` with `'='` and `';'` gives `a=1;b=2`.
+    pub fn write_kv_pairs<K: fmt::Display, V: fmt::Display>(
+        &mut self,
+        pairs: &[(K, V)],
+        kv_sep: char,
+        pair_sep: char,
+    ) -> Result<(), fmt::Error> {
+        use fmt::Write;
+        for (i, (k, v)) in pairs.iter().enumerate() {
+            if i > 0 {
+                self.write_char(pair_sep)?;
+            }
+            self.write_args(format_args!("{}{}{}", k, kv_sep, v))?;
+        }
+
+        Ok(())
+    }
+
+    /// Fails a write that needs `need` bytes in total because it does not fit.
+    ///
+    /// Panics for a writer built with `panicking`, otherwise records
+    /// `WriteError::Overflow`.
+    fn overflow(&mut self, need: usize) -> fmt::Result {
+        if self.panic_on_overflow {
+            overflow_panic(need, self.buf.len());
+        }
+
+        self.fail(WriteError::Overflow)
+    }
+
+    /// Records `err` as the last error and returns `fmt::Error`.
+    fn fail(&mut self, err: WriteError) -> fmt::Result {
+        self.last_error = Some(err);
+        Err(fmt::Error)
+    }
+
+    /// Writes `value` with exactly `decimals` digits after the decimal point.
+    ///
+    /// This avoids the `core::fmt` float formatting code by scaling the value by
+    /// `10^decimals`, rounding half away from zero and printing the integer and
+    /// fractional parts as integers. Results may differ from `{:.N}` in the
+    /// last digit, since the scaling itself is subject to rounding. NaN and
+    /// infinities are written as `NaN`, `inf` and `-inf`. Fails if the scaled
+    /// value does not fit into a `u64`.
+    pub fn write_f32(&mut self, value: f32, decimals: u32) -> Result<(), fmt::Error> {
+        use fmt::Write;
+        if value.is_nan() {
+            return self.write_str("NaN");
+        }
+        if value.is_infinite() {
+            return self.write_str(if value < 0.0 { "-inf" } else { "inf" });
+        }
+
+        let scale = match 10u64.checked_pow(decimals) {
+            Some(scale) => scale,
+            None => return self.fail(WriteError::Format),
+        };
+        let scaled = f64::from(value).abs() * scale as f64 + 0.5;
+        if scaled >= u64::MAX as f64 {
+            return self.fail(WriteError::Format);
+        }
+        let scaled = scaled as u64;
+
+        if value < 0.0 && scaled != 0 {
+            self.write_str("-")?;
+        }
+        write_u64(self, scaled / scale)?;
+        if decimals == 0 {
+            return Ok(());
+        }
+
+        self.write_str(".")?;
+        let frac = scaled % scale;
+        let mut place = scale / 10;
+        while place > 1 && place > frac {
+            self.write_str("0")?;
+            place /= 10;
+        }
+        write_u64(self, frac)
+    }
+
+    /// Formats `args` like `write_fmt`, but stops at the first overflow
+    /// instead of measuring the rest.
+    ///
+    /// An error returned by a formatting trait is recorded as `WriteError::Format`.
+    fn write_args(&mut self, args: fmt::Arguments) -> fmt::Result {
+        let res = fmt::write(self, args);
+        if res.is_err() && self.len <= self.buf.len() {
+            self.last_error = Some(WriteError::Format);
+        }
+
+        res
+    }
+
+    /// Runs `f`, restoring the written length if it fails.
+    ///
+    /// A failure that `f` did not record itself, such as an error returned by
+    /// a `Display` implementation, is recorded as `WriteError::Format`.
+    fn transaction<F>(&mut self, f: F) -> fmt::Result
+    where
+        F: FnOnce(&mut Self) -> fmt::Result,
+    {
+        let len = self.len;
+        let prev_error = self.last_error.take();
+        let res = f(self);
+        if res.is_err() {
+            self.len = len;
+            if self.last_error.is_none() {
+                self.last_error = Some(WriteError::Format);
+            }
+        } else if self.last_error.is_none() {
+            self.last_error = prev_error;
+        }
+
+        res
+    }
+}
+
+impl<'a> fmt::Write for WriteTo<'a> {
+    /// Writes a string slice into the buffer, updating the length accordingly.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.segments += 1;
+        self.write_bytes(s.as_bytes())
+    }
+
+    /// Formats `args` into the buffer, recording the bytes it needed.
+    ///
+    /// Output that does not fit is counted but not stored, see `last_required`.
+    fn write_fmt(&mut self, args: fmt::Arguments) -> fmt::Result {
+        let start = self.len;
+        let measuring = core::mem::replace(&mut self.measuring, true);
+        let res = self.write_args(args);
+        self.measuring = measuring;
+        self.last_required = self.len - start;
+
+        if self.len > self.buf.len() {
+            return self.overflow(self.len);
+        }
+
+        res
+    }
+}
+
+/// Reports an overflow of a writer built with `WriteTo::panicking`.
+///
+/// Kept out of line so the formatted message stays off the hot write path.
+#[cold]
+#[inline(never)]
+fn overflow_panic(need: usize, cap: usize) -> ! {
+    panic!(
+        "format_no_std: buffer overflow (need {}, cap {})",
+        need, cap
+    );
 }
 
 /// Formats data using `format_args!` (`arg` argument) and writes it to a byte buffer `buf`.
 pub fn show<'a>(buf: &'a mut [u8], arg: fmt::Arguments) -> Result<&'a str, fmt::Error> {
-    let mut w = WriteTo::new(buf);
+    finish_show(WriteTo::new(buf), arg)
+}
+
+/// Formats `arg` into an already constructed writer `w` and returns its content.
+///
+/// Unlike `show`, the writer keeps whatever it already holds, so `arg` is
+/// appended to any previously written data.
+pub fn finish_show<'a>(mut w: WriteTo<'a>, arg: fmt::Arguments) -> Result<&'a str, fmt::Error> {
     fmt::write(&mut w, arg)?;
     w.as_str().ok_or(fmt::Error)
 }
 
-#[test]
-fn test() {
-    let mut buf = [0u8; 64];
-    let s = show(&mut buf, format_args!("Test String {}: {}", "foo", 42)).unwrap();
+/// Like `show`, but also returns the 64-bit FNV-1a hash of the written bytes.
+///
+/// The hash is accumulated while formatting, so no second pass over the
+/// buffer is needed. It is deterministic and not meant for cryptographic use.
+pub fn show_hashed<'a>(
+    buf: &'a mut [u8],
+    arg: fmt::Arguments,
+) -> Result<(&'a str, u64), fmt::Error> {
+    let mut w = HashWriter {
+        inner: WriteTo::new(buf),
+        hash: FNV_OFFSET,
+    };
+    fmt::write(&mut w, arg)?;
+    let hash = w.hash;
+    w.inner.as_str().map(|s| (s, hash)).ok_or(fmt::Error)
+}
 
-    assert_eq!("Test String foo: 42", s);
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+struct HashWriter<'a> {
+    inner: WriteTo<'a>,
+    hash: u64,
 }
 
-#[test]
-fn test_to_long() {
-    let mut buf = [0u8; 8];
-    let ret = show(&mut buf, format_args!("Too long string"));
+impl<'a> fmt::Write for HashWriter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_str(s)?;
+        for b in s.bytes() {
+            self.hash = (self.hash ^ u64::from(b)).wrapping_mul(FNV_PRIME);
+        }
 
-    assert_eq!(Err(fmt::Error), ret);
+        Ok(())
+    }
 }
 
-#[test]
-fn test_len() {
+/// Like `show`, but on failure returns the part that fit into `buf`.
+///
+/// The `Err` arm carries the truncated prefix, cut back to a character
+/// boundary so it is always valid UTF-8.
+pub fn show_or_partial<'a>(buf: &'a mut [u8], arg: fmt::Arguments) -> Result<&'a str, &'a str> {
+    let mut w = WriteTo::new(buf);
+    let res = fmt::write(&mut w, arg);
+    let len = w.written_len();
+    let buf: &'a [u8] = w.buf;
+    let s = utf8_prefix(&buf[..len]);
+
+    match res {
+        Ok(()) if s.len() == len => Ok(s),
+        _ => Err(s),
+    }
+}
+
+/// Returns the longest prefix of `bytes` that is valid UTF-8.
+fn utf8_prefix(bytes: &[u8]) -> &str {
+    match from_utf8(bytes) {
+        Ok(s) => s,
+        Err(e) => from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default(),
+    }
+}
+
+/// Writes `t` if `value` is true, otherwise `f`.
+pub fn write_bool(w: &mut WriteTo, value: bool, t: &str, f: &str) -> fmt::Result {
+    fmt::Write::write_str(w, if value { t } else { f })
+}
+
+/// Writes `value` as `"on"` or `"off"`.
+pub fn write_on_off(w: &mut WriteTo, value: bool) -> fmt::Result {
+    write_bool(w, value, "on", "off")
+}
+
+/// Writes `value` as `"yes"` or `"no"`.
+pub fn write_yes_no(w: &mut WriteTo, value: bool) -> fmt::Result {
+    write_bool(w, value, "yes", "no")
+}
+
+/// Writes each of `items` using `Display`, separated by `sep`.
+///
+/// Nothing is written for an empty slice and no separator is added after
+/// the last item.
+pub fn write_join<T: fmt::Display>(w: &mut WriteTo, items: &[T], sep: &str) -> fmt::Result {
     use fmt::Write;
-    let mut buf = [0u8; 64];
-    let mut w = WriteTo::new(&mut buf);
-    write!(&mut w, "Test String {}: {}", "foo", 42).unwrap();
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            w.write_str(sep)?;
+        }
+        w.write_args(format_args!("{}", item))?;
+    }
 
-    assert_eq!(w.len(), Some(19));
-    assert_eq!(w.is_empty(), Some(false));
+    Ok(())
 }
 
-#[test]
-fn test_len_empty() {
+/// Writes `s` with Rust string literal escapes, without the surrounding quotes.
+///
+/// `\n`, `\r`, `\t`, `\0`, `"` and `\` get their backslash escapes and other
+/// ASCII control characters are written as `\xNN`. All other characters,
+/// including non-ASCII ones, are copied unchanged.
+pub fn write_escaped(w: &mut WriteTo, s: &str) -> fmt::Result {
     use fmt::Write;
-    let mut buf = [0u8; 64];
-    let mut w = WriteTo::new(&mut buf);
-    write!(&mut w, "").unwrap();
+    let mut start = 0;
+    for (i, b) in s.bytes().enumerate() {
+        let esc = match b {
+            b'\n' => Some("\\n"),
+            b'\r' => Some("\\r"),
+            b'\t' => Some("\\t"),
+            b'\0' => Some("\\0"),
+            b'"' => Some("\\\""),
+            b'\\' => Some("\\\\"),
+            _ if b.is_ascii_control() => None,
+            _ => continue,
+        };
 
-    assert_eq!(w.len(), Some(0));
-    assert_eq!(w.is_empty(), Some(true));
+        w.write_str(&s[start..i])?;
+        match esc {
+            Some(esc) => w.write_str(esc)?,
+            None => w.write_args(format_args!("\\x{:02x}", b))?,
+        }
+        start = i + 1;
+    }
+
+    w.write_str(&s[start..])
 }
 
-#[test]
-fn test_len_to_long() {
+/// Writes `s` as a valid C identifier.
+///
+/// Every character other than `[A-Za-z0-9_]` is replaced by `_`, and `_` is
+/// prepended if `s` starts with a digit or is empty.
+pub fn write_identifier(w: &mut WriteTo, s: &str) -> fmt::Result {
     use fmt::Write;
-    let mut buf = [0u8; 8];
-    let mut w = WriteTo::new(&mut buf);
-    let res = write!(&mut w, "Tooo long string");
+    if s.is_empty() || s.starts_with(|c: char| c.is_ascii_digit()) {
+        w.write_char('_')?;
+    }
+    for c in s.chars() {
+        w.write_char(if c.is_ascii_alphanumeric() { c } else { '_' })?;
+    }
 
-    assert_eq!(res, Err(core::fmt::Error));
-    assert_eq!(w.len(), None);
-    assert_eq!(w.is_empty(), None);
+    Ok(())
+}
+
+/// Writes `value` in hex, zero-padded to at least `width` digits.
+///
+/// With `prefix` set, `0x` (or `0X` when `uppercase`) is written first; the
+/// prefix does not count towards `width`. Values wider than `width` are
+/// written in full.
+pub fn write_hex_width(
+    w: &mut WriteTo,
+    value: u32,
+    width: usize,
+    uppercase: bool,
+    prefix: bool,
+) -> fmt::Result {
+    match (uppercase, prefix) {
+        (false, false) => w.write_args(format_args!("{:0width$x}", value, width = width)),
+        (false, true) => w.write_args(format_args!("0x{:0width$x}", value, width = width)),
+        (true, false) => w.write_args(format_args!("{:0width$X}", value, width = width)),
+        (true, true) => w.write_args(format_args!("0X{:0width$X}", value, width = width)),
+    }
+}
+
+/// Writes a millisecond count as `HH:MM:SS.mmm`.
+///
+/// Hours are not wrapped and grow beyond two digits as needed.
+pub fn write_duration_ms(w: &mut WriteTo, ms: u64) -> fmt::Result {
+    let (h, m, s) = split_hms(ms / 1000);
+    w.write_args(format_args!("{:02}:{:02}:{:02}.{:03}", h, m, s, ms % 1000))
+}
+
+/// Writes a millisecond count as `HH:MM:SS`, dropping the milliseconds.
+pub fn write_duration_hms(w: &mut WriteTo, ms: u64) -> fmt::Result {
+    let (h, m, s) = split_hms(ms / 1000);
+    w.write_args(format_args!("{:02}:{:02}:{:02}", h, m, s))
+}
+
+fn split_hms(secs: u64) -> (u64, u64, u64) {
+    (secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// The largest frame size supported by `show_chunked`.
+pub const MAX_CHUNK_MTU: usize = 256;
+
+/// Formats `arg` and hands the output to `emit` in frames of exactly `mtu` bytes.
+///
+/// Frames are assembled in a stack buffer of `MAX_CHUNK_MTU` bytes, so the
+/// whole message never has to fit in memory at once. The last partial frame
+/// is padded with `pad`. Nothing is emitted for an empty message. Returns an
+/// error if `mtu` is zero or larger than `MAX_CHUNK_MTU`.
+pub fn show_chunked<F: FnMut(&[u8])>(
+    arg: fmt::Arguments,
+    mtu: usize,
+    pad: u8,
+    emit: F,
+) -> Result<(), fmt::Error> {
+    if mtu == 0 || mtu > MAX_CHUNK_MTU {
+        return Err(fmt::Error);
+    }
+
+    let mut w = ChunkWriter {
+        frame: [0u8; MAX_CHUNK_MTU],
+        mtu,
+        fill: 0,
+        emit,
+    };
+    fmt::write(&mut w, arg)?;
+
+    if w.fill > 0 {
+        for b in &mut w.frame[w.fill..mtu] {
+            *b = pad;
+        }
+        (w.emit)(&w.frame[..mtu]);
+    }
+
+    Ok(())
+}
+
+struct ChunkWriter<F> {
+    frame: [u8; MAX_CHUNK_MTU],
+    mtu: usize,
+    fill: usize,
+    emit: F,
+}
+
+impl<F: FnMut(&[u8])> fmt::Write for ChunkWriter<F> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut raw_s = s.as_bytes();
+        while !raw_s.is_empty() {
+            let num = min(raw_s.len(), self.mtu - self.fill);
+            self.frame[self.fill..self.fill + num].copy_from_slice(&raw_s[..num]);
+            self.fill += num;
+            raw_s = &raw_s[num..];
+
+            if self.fill == self.mtu {
+                (self.emit)(&self.frame[..self.mtu]);
+                self.fill = 0;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Like `show`, but leaves `buf` untouched if the output does not fit.
+///
+/// The length is measured in a first pass and the buffer is only written
+/// once the whole string is known to fit, so `args` is formatted twice.
+pub fn show_atomic<'a>(buf: &'a mut [u8], arg: fmt::Arguments) -> Result<&'a str, fmt::Error> {
+    if measure(arg)? > buf.len() {
+        return Err(fmt::Error);
+    }
+
+    show(buf, arg)
+}
+
+/// Returns the number of bytes `arg` formats to, without storing them.
+fn measure(arg: fmt::Arguments) -> Result<usize, fmt::Error> {
+    let mut c = LenCounter(0);
+    fmt::write(&mut c, arg)?;
+    Ok(c.0)
+}
+
+struct LenCounter(usize);
+
+impl fmt::Write for LenCounter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// Formats `arg` and truncates the output to at most `cols` display columns.
+///
+/// Column widths come from the `unicode-width` crate: wide and fullwidth
+/// characters (CJK, Hangul, emoji presentation) take two columns, combining
+/// marks and control characters none, everything else one. The result is also
+/// cut back to a character boundary if `buf` itself is too small.
+#[cfg(feature = "unicode-width")]
+pub fn show_to_columns<'a>(buf: &'a mut [u8], arg: fmt::Arguments, cols: usize) -> &'a str {
+    let mut w = ColumnWriter {
+        inner: WriteTo::new(buf),
+        cols,
+    };
+    let _ = fmt::write(&mut w, arg);
+    let len = w.inner.written_len();
+    let buf: &'a [u8] = w.inner.buf;
+    utf8_prefix(&buf[..len])
+}
+
+#[cfg(feature = "unicode-width")]
+struct ColumnWriter<'a> {
+    inner: WriteTo<'a>,
+    cols: usize,
+}
+
+#[cfg(feature = "unicode-width")]
+impl<'a> fmt::Write for ColumnWriter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (i, c) in s.char_indices() {
+            let width = char_width(c);
+            if width > self.cols {
+                self.inner.write_str(&s[..i])?;
+                return Err(fmt::Error);
+            }
+            self.cols -= width;
+        }
+
+        self.inner.write_str(s)
+    }
+}
+
+/// Returns the number of display columns taken by `c`.
+#[cfg(feature = "unicode-width")]
+fn char_width(c: char) -> usize {
+    unicode_width::UnicodeWidthChar::width(c).unwrap_or(0)
+}
+
+/// Tries each of `variants` in order and returns the output of the first that fits.
+///
+/// The writer is reset before every attempt, so a variant that overflows
+/// leaves nothing behind. Returns `None` if no variant fits.
+pub fn show_first_fit<'a>(
+    buf: &'a mut [u8],
+    variants: &[&dyn Fn(&mut WriteTo) -> fmt::Result],
+) -> Option<&'a str> {
+    let mut w = WriteTo::new(buf);
+    for variant in variants {
+        w.reset();
+        if variant(&mut w).is_ok() && w.len <= w.buf.len() {
+            return w.as_str();
+        }
+    }
+
+    None
+}
+
+/// Formats `arg` into `primary`, falling back to `fallback` if it does not fit.
+///
+/// On overflow the output is formatted again from scratch into `fallback`,
+/// so `arg` may be evaluated twice. The returned string borrows whichever
+/// buffer holds the complete output.
+pub fn show_spill<'a>(
+    primary: &'a mut [u8],
+    fallback: &'a mut [u8],
+    arg: fmt::Arguments,
+) -> Result<&'a str, fmt::Error> {
+    match show(primary, arg) {
+        Ok(s) => Ok(s),
+        Err(_) => show(fallback, arg),
+    }
+}
+
+/// Describes how much of a formatted message made it into the buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TruncationInfo {
+    /// Whether the output was cut short because the buffer was too small.
+    pub truncated: bool,
+    /// The number of bytes kept in the buffer.
+    pub kept: usize,
+    /// The number of bytes of the full output that did not fit.
+    pub dropped: usize,
+}
+
+/// Formats `arg` into `buf`, reporting where and by how much it was truncated.
+///
+/// The returned string is the complete output or, on overflow, its longest
+/// prefix that fit, cut back to a character boundary. The full overflow is
+/// taken from the length the writer keeps counting past the end of `buf`, so
+/// `arg` is formatted only once. `None` is returned only if a formatting trait
+/// implementation itself failed.
+pub fn show_detailed<'a>(
+    buf: &'a mut [u8],
+    arg: fmt::Arguments,
+) -> (Option<&'a str>, TruncationInfo) {
+    let mut w = WriteTo::new(buf);
+    let res = fmt::Write::write_fmt(&mut w, arg);
+    let len = w.written_len();
+    let required = w.last_required();
+    let truncated = required > len;
+    let buf: &'a [u8] = w.buf;
+    let s = utf8_prefix(&buf[..len]);
+
+    let info = TruncationInfo {
+        truncated,
+        kept: s.len(),
+        dropped: required - s.len(),
+    };
+    match res {
+        Err(_) if !truncated => (None, info),
+        _ => (Some(s), info),
+    }
+}
+
+/// Formats a log line `[HH:MM:SS.mmm] LEVEL message` terminated by `\n`.
+///
+/// The timestamp is written with `write_duration_ms`. For an empty message
+/// the line ends right after the level, without a trailing space.
+pub fn show_log<'a>(
+    buf: &'a mut [u8],
+    ts_ms: u64,
+    level: &str,
+    arg: fmt::Arguments,
+) -> Result<&'a str, fmt::Error> {
+    use fmt::Write;
+    let mut w = WriteTo::new(buf);
+    w.write_str("[")?;
+    write_duration_ms(&mut w, ts_ms)?;
+    w.write_str("] ")?;
+    w.write_str(level)?;
+
+    let mark = w.len;
+    w.write_str(" ")?;
+    fmt::write(&mut w, arg)?;
+    if w.len == mark + 1 {
+        w.len = mark;
+    }
+
+    w.write_str("\n")?;
+    w.as_str().ok_or(fmt::Error)
+}
+
+/// Formats `arg` after a `len_bytes` wide length prefix and returns the whole frame.
+///
+/// The payload length is filled into the prefix once formatting is done, in
+/// little- or big-endian byte order. Fails if `len_bytes` is not between 1
+/// and 8, if the frame does not fit into `buf` or if the payload length
+/// cannot be represented in `len_bytes` bytes.
+pub fn show_length_prefixed<'a>(
+    buf: &'a mut [u8],
+    len_bytes: usize,
+    little_endian: bool,
+    arg: fmt::Arguments,
+) -> Result<&'a [u8], fmt::Error> {
+    if len_bytes == 0 || len_bytes > 8 || len_bytes > buf.len() {
+        return Err(fmt::Error);
+    }
+
+    let (prefix, payload) = buf.split_at_mut(len_bytes);
+    let len = show(payload, arg)?.len() as u64;
+    if len_bytes < 8 && len >> (8 * len_bytes) != 0 {
+        return Err(fmt::Error);
+    }
+
+    let bytes = len.to_le_bytes();
+    for (i, b) in prefix.iter_mut().enumerate() {
+        *b = if little_endian {
+            bytes[i]
+        } else {
+            bytes[len_bytes - 1 - i]
+        };
+    }
+
+    let buf: &'a [u8] = buf;
+    Ok(&buf[..len_bytes + len as usize])
+}
+
+/// Writes `items` separated by `sep` until the buffer is full.
+///
+/// Items are formatted lazily and iteration stops at the first item that
+/// does not fit completely; that item and its separator are removed again.
+/// Returns the written list and the number of items it contains.
+pub fn show_items<'a, I>(buf: &'a mut [u8], items: I, sep: &str) -> (&'a str, usize)
+where
+    I: IntoIterator,
+    I::Item: fmt::Display,
+{
+    use fmt::Write;
+    let mut w = WriteTo::new(buf);
+    let mut count = 0;
+    for item in items {
+        let res = w.transaction(|w| {
+            if count > 0 {
+                w.write_str(sep)?;
+            }
+            w.write_args(format_args!("{}", item))
+        });
+        if res.is_err() {
+            break;
+        }
+        count += 1;
+    }
+
+    (w.as_str().unwrap_or_default(), count)
+}
+
+/// Formats `arg` into `buf`, ending the output with `…` if it had to be cut.
+///
+/// See `show_ellipsized_with` for a custom marker.
+pub fn show_ellipsized<'a>(buf: &'a mut [u8], arg: fmt::Arguments) -> &'a str {
+    show_ellipsized_with(buf, arg, "…")
+}
+
+/// Formats `arg` into `buf`, ending the output with `marker` if it had to be cut.
+///
+/// On overflow the output is truncated on a character boundary so that the
+/// marker fits into the buffer behind it. The result is always valid UTF-8;
+/// it is empty if `buf` cannot even hold the marker. If a formatting trait
+/// fails without running out of space, the output written up to that point
+/// is returned without the marker.
+pub fn show_ellipsized_with<'a>(buf: &'a mut [u8], arg: fmt::Arguments, marker: &str) -> &'a str {
+    let mut w = WriteTo::new(buf);
+    let _ = fmt::write(&mut w, arg);
+    let len = w.written_len();
+    let truncated = w.len > len;
+    let buf = w.buf;
+
+    let end = if !truncated {
+        len
+    } else if marker.len() > buf.len() {
+        0
+    } else {
+        let keep = utf8_prefix(&buf[..min(len, buf.len() - marker.len())]).len();
+        buf[keep..keep + marker.len()].copy_from_slice(marker.as_bytes());
+        keep + marker.len()
+    };
+
+    let buf: &'a [u8] = buf;
+    utf8_prefix(&buf[..end])
+}
+
+/// A writer that collapses any run of consecutive `\n` into a single `\n`.
+///
+/// The state is kept across `write_str` calls, so fragments that each end
+/// with a newline do not produce blank lines when concatenated.
+pub struct NormalizeNewlineWriter<'a> {
+    inner: WriteTo<'a>,
+    newline: bool,
+}
+
+impl<'a> NormalizeNewlineWriter<'a> {
+    /// Constructs a new `NormalizeNewlineWriter` wrapping the provided byte buffer.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        NormalizeNewlineWriter {
+            inner: WriteTo::new(buf),
+            newline: false,
+        }
+    }
+
+    /// Converts the written portion of the buffer into a string slice, if possible.
+    pub fn as_str(self) -> Option<&'a str> {
+        self.inner.as_str()
+    }
+}
+
+impl<'a> fmt::Write for NormalizeNewlineWriter<'a> {
+    /// Writes a string slice, dropping newlines that follow another newline.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (i, part) in s.split('\n').enumerate() {
+            if i > 0 && !self.newline {
+                self.inner.write_str("\n")?;
+                self.newline = true;
+            }
+            if !part.is_empty() {
+                self.inner.write_str(part)?;
+                self.newline = false;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A writer that translates every lone `\n` into `\r\n`.
+///
+/// An already present `\r\n` is copied unchanged, also when the `\r` and the
+/// `\n` arrive in separate `write_str` calls. The extra `\r` bytes count
+/// towards the buffer capacity.
+pub struct CrlfWriter<'a> {
+    inner: WriteTo<'a>,
+    cr: bool,
+}
+
+impl<'a> CrlfWriter<'a> {
+    /// Constructs a new `CrlfWriter` wrapping the provided byte buffer.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        CrlfWriter {
+            inner: WriteTo::new(buf),
+            cr: false,
+        }
+    }
+
+    /// Converts the written portion of the buffer into a string slice, if possible.
+    pub fn as_str(self) -> Option<&'a str> {
+        self.inner.as_str()
+    }
+}
+
+impl<'a> fmt::Write for CrlfWriter<'a> {
+    /// Writes a string slice, inserting `\r` before each `\n` not preceded by one.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (i, part) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.inner.write_str(if self.cr { "\n" } else { "\r\n" })?;
+                self.cr = false;
+            }
+            if !part.is_empty() {
+                self.inner.write_str(part)?;
+                self.cr = part.ends_with('\r');
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A writer that inserts a prefix at the start of every line.
+///
+/// The prefix is written before the first content and after each `\n` once
+/// more content follows, tracking the line state across `write_str` calls.
+pub struct LinePrefixWriter<'a> {
+    inner: WriteTo<'a>,
+    prefix: &'a str,
+    line_start: bool,
+}
+
+impl<'a> LinePrefixWriter<'a> {
+    /// Constructs a new `LinePrefixWriter` wrapping the provided byte buffer.
+    pub fn new(buf: &'a mut [u8], prefix: &'a str) -> Self {
+        LinePrefixWriter {
+            inner: WriteTo::new(buf),
+            prefix,
+            line_start: true,
+        }
+    }
+
+    /// Converts the written portion of the buffer into a string slice, if possible.
+    pub fn as_str(self) -> Option<&'a str> {
+        self.inner.as_str()
+    }
+}
+
+impl<'a> fmt::Write for LinePrefixWriter<'a> {
+    /// Writes a string slice, prefixing each line that starts in it.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for line in s.split_inclusive('\n') {
+            if self.line_start {
+                self.inner.write_str(self.prefix)?;
+            }
+            self.inner.write_str(line)?;
+            self.line_start = line.ends_with('\n');
+        }
+
+        Ok(())
+    }
+}
+
+/// A writer that hands every completed line to a callback.
+///
+/// Content is accumulated in the buffer until a `\n` is written; the line is
+/// then passed to `sink` without the trailing newline and the buffer is
+/// cleared. A trailing partial line stays buffered until `flush`. A single
+/// line longer than the buffer is passed to `sink` truncated and makes
+/// `write_str` fail; the lines after it are handled normally.
+pub struct LineBufferedWriter<'a, F: FnMut(&str)> {
+    inner: WriteTo<'a>,
+    sink: F,
+}
+
+impl<'a, F: FnMut(&str)> LineBufferedWriter<'a, F> {
+    /// Constructs a new `LineBufferedWriter` wrapping the provided byte buffer.
+    pub fn new(buf: &'a mut [u8], sink: F) -> Self {
+        LineBufferedWriter {
+            inner: WriteTo::new(buf),
+            sink,
+        }
+    }
+
+    /// Passes any buffered partial line to the callback and clears the buffer.
+    pub fn flush(&mut self) {
+        if self.inner.len != 0 {
+            self.emit();
+        }
+    }
+
+    fn emit(&mut self) {
+        let len = self.inner.written_len();
+        (self.sink)(utf8_prefix(&self.inner.buf[..len]));
+        self.inner.reset();
+    }
+}
+
+impl<'a, F: FnMut(&str)> fmt::Write for LineBufferedWriter<'a, F> {
+    /// Writes a string slice, emitting each line completed by it.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut res = Ok(());
+        for line in s.split_inclusive('\n') {
+            match line.strip_suffix('\n') {
+                Some(text) => {
+                    if self.inner.write_str(text).is_err() {
+                        res = Err(fmt::Error);
+                    }
+                    self.emit();
+                }
+                None => self.inner.write_str(line)?,
+            }
+        }
+
+        res
+    }
+}
+
+/// A writer that fills its buffer from the back towards the front.
+///
+/// Each `prepend_str` places its text before everything written so far, which
+/// suits building output from the least significant end.
+pub struct RevWriter<'a> {
+    buf: &'a mut [u8],
+    start: usize,
+}
+
+impl<'a> RevWriter<'a> {
+    /// Constructs a new `RevWriter` wrapping the provided byte buffer.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        let start = buf.len();
+        RevWriter { buf, start }
+    }
+
+    /// Writes `s` in front of the current content. Nothing is written if it does not fit.
+    pub fn prepend_str(&mut self, s: &str) -> fmt::Result {
+        let raw_s = s.as_bytes();
+        if raw_s.len() > self.start {
+            return Err(fmt::Error);
+        }
+
+        self.start -= raw_s.len();
+        self.buf[self.start..self.start + raw_s.len()].copy_from_slice(raw_s);
+        Ok(())
+    }
+
+    /// Converts the written portion of the buffer into a string slice, if possible.
+    pub fn as_str(self) -> Option<&'a str> {
+        from_utf8(&self.buf[self.start..]).ok()
+    }
+}
+
+/// A writer that expands each `\t` into spaces up to the next tab stop.
+///
+/// Tab stops are every `tab_width` columns. The current column is counted in
+/// characters and kept across `write_str` calls; a `\n` resets it to zero. A
+/// `tab_width` of zero drops tabs.
+pub struct TabExpandWriter<'a> {
+    inner: WriteTo<'a>,
+    tab_width: usize,
+    col: usize,
+}
+
+impl<'a> TabExpandWriter<'a> {
+    /// Constructs a new `TabExpandWriter` wrapping the provided byte buffer.
+    pub fn new(buf: &'a mut [u8], tab_width: usize) -> Self {
+        TabExpandWriter {
+            inner: WriteTo::new(buf),
+            tab_width,
+            col: 0,
+        }
+    }
+
+    /// Converts the written portion of the buffer into a string slice, if possible.
+    pub fn as_str(self) -> Option<&'a str> {
+        self.inner.as_str()
+    }
+}
+
+impl<'a> fmt::Write for TabExpandWriter<'a> {
+    /// Writes a string slice, replacing tabs with spaces.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (i, part) in s.split('\t').enumerate() {
+            if i > 0 && self.tab_width > 0 {
+                let spaces = self.tab_width - self.col % self.tab_width;
+                for _ in 0..spaces {
+                    self.inner.write_str(" ")?;
+                }
+                self.col += spaces;
+            }
+
+            self.inner.write_str(part)?;
+            match part.rfind('\n') {
+                Some(n) => self.col = part[n + 1..].chars().count(),
+                None => self.col += part.chars().count(),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A writer that lets callers record byte offsets between the fields they write.
+///
+/// `mark` returns the current end of the written content. Offsets taken
+/// before and after a field can be used to slice that field back out of the
+/// final string without copying.
+pub struct RecordingWriter<'a> {
+    inner: WriteTo<'a>,
+}
+
+impl<'a> RecordingWriter<'a> {
+    /// Constructs a new `RecordingWriter` wrapping the provided byte buffer.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        RecordingWriter {
+            inner: WriteTo::new(buf),
+        }
+    }
+
+    /// Returns the byte offset at which the next write will start.
+    pub fn mark(&mut self) -> usize {
+        self.inner.written_len()
+    }
+
+    /// Converts the written portion of the buffer into a string slice, if possible.
+    pub fn as_str(self) -> Option<&'a str> {
+        self.inner.as_str()
+    }
+}
+
+impl<'a> fmt::Write for RecordingWriter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_str(s)
+    }
+}
+
+/// A writer that counts the visible characters written, ignoring control characters.
+///
+/// Only the control characters themselves are skipped, so for a terminal
+/// escape sequence such as `\x1b[31m` just the `ESC` byte is left out and the
+/// rest of it is counted. Of a write that does not fit completely, only the
+/// characters actually stored in the buffer are counted.
+pub struct DisplayWidthWriter<'a> {
+    inner: WriteTo<'a>,
+    visible_len: usize,
+}
+
+impl<'a> DisplayWidthWriter<'a> {
+    /// Constructs a new `DisplayWidthWriter` wrapping the provided byte buffer.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        DisplayWidthWriter {
+            inner: WriteTo::new(buf),
+            visible_len: 0,
+        }
+    }
+
+    /// Returns the number of non-control characters written.
+    pub fn visible_len(&self) -> usize {
+        self.visible_len
+    }
+
+    /// Converts the written portion of the buffer into a string slice, if possible.
+    pub fn as_str(self) -> Option<&'a str> {
+        self.inner.as_str()
+    }
+}
+
+impl<'a> fmt::Write for DisplayWidthWriter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let start = self.inner.written_len();
+        let res = self.inner.write_str(s);
+        let stored = self.inner.written_len() - start;
+        let stored = utf8_prefix(&s.as_bytes()[..stored]);
+        self.visible_len += stored.chars().filter(|c| !c.is_control()).count();
+        res
+    }
+}
+
+/// A writer that counts how many fields were written completely before an overflow.
+///
+/// Call `field_break` after writing each field. A field only counts if the
+/// writer had not overflowed when its break was marked, so `fields_written`
+/// tells which field was the first to be cut.
+pub struct FieldWriter<'a> {
+    inner: WriteTo<'a>,
+    fields: usize,
+}
+
+impl<'a> FieldWriter<'a> {
+    /// Constructs a new `FieldWriter` wrapping the provided byte buffer.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        FieldWriter {
+            inner: WriteTo::new(buf),
+            fields: 0,
+        }
+    }
+
+    /// Marks the end of a field.
+    pub fn field_break(&mut self) {
+        if self.inner.len <= self.inner.buf.len() {
+            self.fields += 1;
+        }
+    }
+
+    /// Returns the number of fields fully written before the first overflow.
+    pub fn fields_written(&self) -> usize {
+        self.fields
+    }
+
+    /// Converts the written portion of the buffer into a string slice, if possible.
+    pub fn as_str(self) -> Option<&'a str> {
+        self.inner.as_str()
+    }
+}
+
+impl<'a> fmt::Write for FieldWriter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_str(s)
+    }
+}
+
+/// A writer that hard-wraps lines longer than a fixed limit.
+///
+/// A `\n` is inserted before the character that would make the current line
+/// exceed `max_line` characters, regardless of word boundaries. Lengths are
+/// counted in characters so a break never splits a multi-byte character.
+/// The line length is kept across `write_str` calls. A `max_line` of zero
+/// disables wrapping.
+pub struct MaxLineWriter<'a> {
+    inner: WriteTo<'a>,
+    max_line: usize,
+    col: usize,
+}
+
+impl<'a> MaxLineWriter<'a> {
+    /// Constructs a new `MaxLineWriter` wrapping the provided byte buffer.
+    pub fn new(buf: &'a mut [u8], max_line: usize) -> Self {
+        MaxLineWriter {
+            inner: WriteTo::new(buf),
+            max_line,
+            col: 0,
+        }
+    }
+
+    /// Converts the written portion of the buffer into a string slice, if possible.
+    pub fn as_str(self) -> Option<&'a str> {
+        self.inner.as_str()
+    }
+}
+
+impl<'a> fmt::Write for MaxLineWriter<'a> {
+    /// Writes a string slice, breaking lines that reach the limit.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            if c == '\n' {
+                self.col = 0;
+                continue;
+            }
+            if self.max_line != 0 && self.col == self.max_line {
+                self.inner.write_str(&s[start..i])?;
+                self.inner.write_str("\n")?;
+                start = i;
+                self.col = 0;
+            }
+            self.col += 1;
+        }
+
+        self.inner.write_str(&s[start..])
+    }
+}
+
+/// A writer that passes every byte through `f` before storing it.
+///
+/// This allows charset remapping or simple encodings such as ROT13 at format
+/// time. `f` sees the raw UTF-8 bytes, so mapping non-ASCII bytes or mapping
+/// ASCII to non-ASCII can produce invalid UTF-8; `as_str` re-validates the
+/// content and returns `None` in that case. An `f` that maps ASCII to ASCII
+/// and leaves other bytes alone always keeps the content valid.
+pub struct MappingWriter<'a, F: FnMut(u8) -> u8> {
+    inner: WriteTo<'a>,
+    f: F,
+}
+
+impl<'a, F: FnMut(u8) -> u8> MappingWriter<'a, F> {
+    /// Constructs a new `MappingWriter` wrapping the provided byte buffer.
+    pub fn new(buf: &'a mut [u8], f: F) -> Self {
+        MappingWriter {
+            inner: WriteTo::new(buf),
+            f,
+        }
+    }
+
+    /// Converts the written portion of the buffer into a string slice, if possible.
+    pub fn as_str(self) -> Option<&'a str> {
+        self.inner.as_str()
+    }
+}
+
+impl<'a, F: FnMut(u8) -> u8> fmt::Write for MappingWriter<'a, F> {
+    /// Writes a string slice, mapping each byte through `f`.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut chunk = [0u8; 32];
+        for part in s.as_bytes().chunks(chunk.len()) {
+            for (dst, &src) in chunk.iter_mut().zip(part) {
+                *dst = (self.f)(src);
+            }
+            self.inner.write_bytes(&chunk[..part.len()])?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A writer that drops bytes rejected by a predicate.
+///
+/// To keep the content valid UTF-8 the predicate is applied per character:
+/// a character is kept only if `keep` returns true for every byte of its
+/// UTF-8 encoding, otherwise it is dropped as a whole. For ASCII this is
+/// simply a per-byte filter, e.g. for stripping XON/XOFF (`0x11`/`0x13`).
+pub struct FilterWriter<'a, F: Fn(u8) -> bool> {
+    inner: WriteTo<'a>,
+    keep: F,
+}
+
+impl<'a, F: Fn(u8) -> bool> FilterWriter<'a, F> {
+    /// Constructs a new `FilterWriter` wrapping the provided byte buffer.
+    pub fn new(buf: &'a mut [u8], keep: F) -> Self {
+        FilterWriter {
+            inner: WriteTo::new(buf),
+            keep,
+        }
+    }
+
+    /// Converts the written portion of the buffer into a string slice, if possible.
+    pub fn as_str(self) -> Option<&'a str> {
+        self.inner.as_str()
+    }
+}
+
+impl<'a, F: Fn(u8) -> bool> fmt::Write for FilterWriter<'a, F> {
+    /// Writes a string slice, leaving out the rejected characters.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            let end = i + c.len_utf8();
+            if !s.as_bytes()[i..end].iter().all(|&b| (self.keep)(b)) {
+                self.inner.write_str(&s[start..i])?;
+                start = end;
+            }
+        }
+
+        self.inner.write_str(&s[start..])
+    }
+}
+
+/// A string stored inline in a fixed `[u8; N]` array.
+///
+/// Writes that do not fit are rejected as a whole, so the content is always
+/// valid UTF-8. Unlike the `&str` returned by `show`, an `ArrayString` owns
+/// its storage and can be returned from a function.
+#[derive(Clone, Copy)]
+pub struct ArrayString<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> ArrayString<N> {
+    /// Constructs a new, empty `ArrayString`.
+    pub const fn new() -> Self {
+        ArrayString {
+            buf: [0u8; N],
+            len: 0,
+        }
+    }
+
+    /// Returns the content as a string slice.
+    pub fn as_str(&self) -> &str {
+        from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+
+    /// Get the number of bytes stored.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no bytes are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<const N: usize> Default for ArrayString<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Write for ArrayString<N> {
+    /// Appends a string slice if it fits entirely, leaving the content unchanged otherwise.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let raw_s = s.as_bytes();
+        if raw_s.len() > N - self.len {
+            return Err(fmt::Error);
+        }
+
+        self.buf[self.len..self.len + raw_s.len()].copy_from_slice(raw_s);
+        self.len += raw_s.len();
+        Ok(())
+    }
+}
+
+impl<const N: usize> fmt::Display for ArrayString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> fmt::Debug for ArrayString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// Formats `arg` into a new `ArrayString` of capacity `N`.
+///
+/// The string returned by `show` borrows the buffer, so it cannot outlive a
+/// local buffer:
+///
+/// ``` compile_fail,E0515
+/// fn label(id: u32) -> &'static str {
+///     let mut buf = [0u8; 16];
+///     format_no_std::show(&mut buf, format_args!("id {}", id)).unwrap()
+/// }
+/// ```
+///
+/// ``` compile_fail,E0597
+/// let s;
+/// {
+///     let mut buf = [0u8; 16];
+///     s = format_no_std::show(&mut buf, format_args!("id {}", 1)).unwrap();
+/// }
+/// assert_eq!(s, "id 1");
+/// ```
+///
+/// Return an owned `ArrayString` instead:
+///
+/// ``` rust
+/// use format_no_std::ArrayString;
+///
+/// fn label(id: u32) -> ArrayString<16> {
+///     format_no_std::show_owned(format_args!("id {}", id)).unwrap()
+/// }
+///
+/// assert_eq!(label(7).as_str(), "id 7");
+/// ```
+pub fn show_owned<const N: usize>(arg: fmt::Arguments) -> Result<ArrayString<N>, fmt::Error> {
+    let mut s = ArrayString::new();
+    fmt::write(&mut s, arg)?;
+    Ok(s)
+}
+
+/// A formatting buffer that owns its storage and can be placed in a `static`.
+///
+/// `new` is a `const fn`, so a single scratch buffer can be shared by the
+/// whole firmware instead of passing `&mut [u8]` down every call stack.
+/// Messages are built up with `format` and cleared with `reset`; a `format`
+/// that does not fit leaves the previous content unchanged.
+///
+/// The type does no synchronization of its own. Keep it in a `static mut`
+/// only on a single-core system and only access it from one execution
+/// context (for example never from both `main` and an interrupt handler),
+/// or wrap it in a critical-section mutex such as
+/// `cortex_m::interrupt::Mutex<RefCell<StaticWriter<N>>>`.
+///
+/// ``` rust
+/// use format_no_std::StaticWriter;
+///
+/// static mut SCRATCH: StaticWriter<64> = StaticWriter::new();
+///
+/// // Safety: only ever accessed from this single thread.
+/// let w = unsafe { &mut *core::ptr::addr_of_mut!(SCRATCH) };
+/// w.format(format_args!("id={} ", 1)).unwrap();
+/// let s = w.format(format_args!("ok")).unwrap();
+///
+/// assert_eq!(s, "id=1 ok");
+/// ```
+pub struct StaticWriter<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> StaticWriter<N> {
+    /// Constructs a new, empty `StaticWriter`.
+    pub const fn new() -> Self {
+        StaticWriter {
+            buf: [0u8; N],
+            len: 0,
+        }
+    }
+
+    /// Appends `arg` to the content and returns the whole content.
+    pub fn format(&mut self, arg: fmt::Arguments) -> Result<&str, fmt::Error> {
+        let mut w = WriteTo::new(&mut self.buf[self.len..]);
+        fmt::write(&mut w, arg)?;
+        self.len += w.len().ok_or(fmt::Error)?;
+        self.as_str().ok_or(fmt::Error)
+    }
+
+    /// Returns the content as a string slice, if it is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        from_utf8(&self.buf[..self.len]).ok()
+    }
+
+    /// Clears the content.
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Default for StaticWriter<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A lightweight alternative to `fmt::Display` that writes straight into a `WriteTo`.
+///
+/// Implementations do not go through `fmt::Arguments` or `fmt::Formatter`,
+/// so the values are written without the `core::fmt` code behind
+/// `format_args!`. Integers up to 32 bits are printed with 32-bit arithmetic.
+///
+/// Showing a `u32` on `thumbv7em-none-eabihf` (`opt-level = "s"`, LTO,
+/// silent panic handler) took 1452 bytes of `.text` with `show_t` against
+/// 2434 bytes with `show` and `format_args!`. A panic handler that prints its
+/// message links the integer formatting back in for core's bounds checks, and
+/// the gap shrinks to 2620 against 2730 bytes.
+pub trait ShowBytes {
+    /// Writes `self` into `w`.
+    fn show_bytes(&self, w: &mut WriteTo) -> fmt::Result;
+}
+
+impl<T: ShowBytes + ?Sized> ShowBytes for &T {
+    fn show_bytes(&self, w: &mut WriteTo) -> fmt::Result {
+        (**self).show_bytes(w)
+    }
+}
+
+impl ShowBytes for str {
+    fn show_bytes(&self, w: &mut WriteTo) -> fmt::Result {
+        fmt::Write::write_str(w, self)
+    }
+}
+
+impl ShowBytes for char {
+    fn show_bytes(&self, w: &mut WriteTo) -> fmt::Result {
+        fmt::Write::write_str(w, self.encode_utf8(&mut [0u8; 4]))
+    }
+}
+
+impl ShowBytes for bool {
+    fn show_bytes(&self, w: &mut WriteTo) -> fmt::Result {
+        fmt::Write::write_str(w, if *self { "true" } else { "false" })
+    }
+}
+
+macro_rules! show_bytes_unsigned {
+    ($($t:ty => $write:ident),*) => {$(
+        impl ShowBytes for $t {
+            fn show_bytes(&self, w: &mut WriteTo) -> fmt::Result {
+                $write(w, *self as _)
+            }
+        }
+    )*};
+}
+
+macro_rules! show_bytes_signed {
+    ($($t:ty => $write:ident),*) => {$(
+        impl ShowBytes for $t {
+            fn show_bytes(&self, w: &mut WriteTo) -> fmt::Result {
+                if *self < 0 {
+                    fmt::Write::write_str(w, "-")?;
+                }
+                $write(w, self.unsigned_abs() as _)
+            }
+        }
+    )*};
+}
+
+show_bytes_unsigned!(u8 => write_u32, u16 => write_u32, u32 => write_u32, u64 => write_u64, usize => write_u64);
+show_bytes_unsigned!(u128 => write_u128);
+show_bytes_signed!(i8 => write_u32, i16 => write_u32, i32 => write_u32, i64 => write_u64, isize => write_u64);
+show_bytes_signed!(i128 => write_u128);
+
+macro_rules! write_unsigned_fn {
+    ($($name:ident: $t:ty),*) => {$(
+        /// Writes the decimal digits of `value`.
+        fn $name(w: &mut WriteTo, mut value: $t) -> fmt::Result {
+            let mut digits = [0u8; 39];
+            let mut start = digits.len();
+            loop {
+                start -= 1;
+                digits[start] = b'0' + (value % 10) as u8;
+                value /= 10;
+                if value == 0 {
+                    break;
+                }
+            }
+
+            fmt::Write::write_str(w, from_utf8(&digits[start..]).unwrap_or_default())
+        }
+    )*};
+}
+
+write_unsigned_fn!(write_u32: u32, write_u64: u64, write_u128: u128);
+
+/// Writes `val` into `buf` using its `ShowBytes` implementation.
+pub fn show_t<T: ShowBytes>(buf: &mut [u8], val: T) -> Result<&str, fmt::Error> {
+    let mut w = WriteTo::new(buf);
+    val.show_bytes(&mut w)?;
+    w.as_str().ok_or(fmt::Error)
+}
+
+/// Formats into a fixed-size array like `show`, checking at compile time that
+/// the literal part of the format string fits into the array.
+///
+/// Only the text outside `{...}` placeholders is counted, so a passing check
+/// does not guarantee that the formatted arguments fit as well; that is
+/// still reported at runtime by the returned `Result`. The buffer must be an
+/// array place of type `[u8; N]`.
+///
+/// ``` rust
+/// let mut buf = [0u8; 16];
+/// let (a, b) = (1, 2);
+/// let s = format_no_std::cformat!(buf, "a={}, b={}", a, b).unwrap();
+///
+/// assert_eq!(s, "a=1, b=2");
+/// ```
+///
+/// A buffer that cannot even hold the literal text fails to build. The check
+/// is a constant evaluated when the call is monomorphized, so it is reported
+/// by `cargo build` but not by `cargo check`, clippy or rust-analyzer:
+///
+/// ``` compile_fail,E0080
+/// let mut buf = [0u8; 4];
+/// let _ = format_no_std::cformat!(buf, "value: {}", 42);
+/// ```
+#[macro_export]
+macro_rules! cformat {
+    ($buf:expr, $fmt:literal $($args:tt)*) => {
+        $crate::show(
+            $crate::LiteralFits::<{ $crate::literal_len($fmt) }>::check(&mut $buf),
+            format_args!($fmt $($args)*),
+        )
+    };
+}
+
+/// Returns the number of bytes a format string produces outside of its
+/// placeholders, counting `{{` and `}}` as one byte each.
+#[doc(hidden)]
+pub const fn literal_len(fmt: &str) -> usize {
+    let bytes = fmt.as_bytes();
+    let mut len = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'{' && i + 1 < bytes.len() && bytes[i + 1] != b'{' {
+            while i < bytes.len() && bytes[i] != b'}' {
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
+        if (bytes[i] == b'{' || bytes[i] == b'}') && i + 1 < bytes.len() && bytes[i + 1] == bytes[i]
+        {
+            i += 1;
+        }
+        len += 1;
+        i += 1;
+    }
+
+    len
+}
+
+/// Compile-time check used by `cformat!` that `L` literal bytes fit into `[u8; N]`.
+#[doc(hidden)]
+pub struct LiteralFits<const L: usize>;
+
+impl<const L: usize> LiteralFits<L> {
+    #[doc(hidden)]
+    pub fn check<const N: usize>(buf: &mut [u8; N]) -> &mut [u8] {
+        #[allow(clippy::let_unit_value)]
+        let () = AssertFits::<L, N>::OK;
+        buf
+    }
+}
+
+struct AssertFits<const L: usize, const N: usize>;
+
+impl<const L: usize, const N: usize> AssertFits<L, N> {
+    const OK: () = assert!(
+        L <= N,
+        "cformat!: format string literal does not fit into the buffer"
+    );
+}
+
+#[test]
+fn test() {
+    let mut buf = [0u8; 64];
+    let s = show(&mut buf, format_args!("Test String {}: {}", "foo", 42)).unwrap();
+
+    assert_eq!("Test String foo: 42", s);
+}
+
+#[test]
+fn test_to_long() {
+    let mut buf = [0u8; 8];
+    let ret = show(&mut buf, format_args!("Too long string"));
+
+    assert_eq!(Err(fmt::Error), ret);
+}
+
+#[test]
+#[allow(clippy::write_literal)]
+fn test_len() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "Test String {}: {}", "foo", 42).unwrap();
+
+    assert_eq!(w.len(), Some(19));
+    assert_eq!(w.is_empty(), Some(false));
+}
+
+#[test]
+fn test_len_empty() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "").unwrap();
+
+    assert_eq!(w.len(), Some(0));
+    assert_eq!(w.is_empty(), Some(true));
+}
+
+#[test]
+fn test_len_to_long() {
+    use fmt::Write;
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::new(&mut buf);
+    let res = write!(&mut w, "Tooo long string");
+
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(w.len(), None);
+    assert_eq!(w.is_empty(), None);
+}
+
+#[test]
+fn test_normalize_newline() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = NormalizeNewlineWriter::new(&mut buf);
+    write!(&mut w, "a\n\n\nb").unwrap();
+
+    assert_eq!(w.as_str(), Some("a\nb"));
+}
+
+#[test]
+fn test_normalize_newline_across_writes() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = NormalizeNewlineWriter::new(&mut buf);
+    w.write_str("a\n").unwrap();
+    w.write_str("\n").unwrap();
+    w.write_str("\nb\n").unwrap();
+
+    assert_eq!(w.as_str(), Some("a\nb\n"));
+}
+
+#[test]
+fn test_finish() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "Test {}", 42).unwrap();
+
+    assert_eq!(w.finish(), (Some("Test 42"), 7, 64));
+}
+
+#[test]
+fn test_finish_to_long() {
+    use fmt::Write;
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::new(&mut buf);
+    let res = write!(&mut w, "Tooo long string");
+
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(w.finish(), (None, 8, 8));
+}
+
+#[test]
+fn test_crlf() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = CrlfWriter::new(&mut buf);
+    write!(&mut w, "a\nb\r\nc\n").unwrap();
+
+    assert_eq!(w.as_str(), Some("a\r\nb\r\nc\r\n"));
+}
+
+#[test]
+fn test_crlf_split_across_writes() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = CrlfWriter::new(&mut buf);
+    w.write_str("a\r").unwrap();
+    w.write_str("\nb").unwrap();
+
+    assert_eq!(w.as_str(), Some("a\r\nb"));
+}
+
+#[test]
+fn test_crlf_to_long() {
+    use fmt::Write;
+    let mut buf = [0u8; 3];
+    let mut w = CrlfWriter::new(&mut buf);
+    let res = w.write_str("ab\n");
+
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(w.as_str(), None);
+}
+
+#[test]
+fn test_write_framed() {
+    let mut buf = [0u8; 16];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_framed(format_args!("hi {}", 7)).unwrap();
+
+    assert_eq!(w.len(), Some(6));
+    assert_eq!(&buf[..6], b"\x04\x00hi 7");
+}
+
+#[test]
+fn test_write_framed_to_long() {
+    let mut buf = [0u8; 6];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_framed(format_args!("a")).unwrap();
+    let res = w.write_framed(format_args!("bcd"));
+
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(w.len(), Some(3));
+}
+
+#[test]
+fn test_write_framed_after_advance_past_end() {
+    let mut buf = [0u8; 6];
+    let mut w = WriteTo::new(&mut buf);
+    w.advance(usize::MAX);
+    let res = w.write_framed(format_args!("a"));
+
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(w.last_error(), Some(WriteError::Overflow));
+}
+
+#[test]
+fn test_show_hashed() {
+    let mut buf = [0u8; 64];
+    let (s, hash) = show_hashed(&mut buf, format_args!("{}", "a")).unwrap();
+
+    assert_eq!(s, "a");
+    assert_eq!(hash, 0xaf63_dc4c_8601_ec8c);
+
+    let mut buf = [0u8; 64];
+    let (s, hash) = show_hashed(&mut buf, format_args!("")).unwrap();
+
+    assert_eq!(s, "");
+    assert_eq!(hash, FNV_OFFSET);
+}
+
+#[test]
+fn test_show_hashed_same_content() {
+    let mut buf1 = [0u8; 64];
+    let mut buf2 = [0u8; 64];
+    let (_, h1) = show_hashed(&mut buf1, format_args!("foo {}", 42)).unwrap();
+    let (_, h2) = show_hashed(&mut buf2, format_args!("foo {}{}", 4, 2)).unwrap();
+
+    assert_eq!(h1, h2);
+}
+
+#[test]
+fn test_show_hashed_to_long() {
+    let mut buf = [0u8; 8];
+    let ret = show_hashed(&mut buf, format_args!("Too long string"));
+
+    assert_eq!(Err(fmt::Error), ret);
+}
+
+#[test]
+fn test_show_or_partial() {
+    let mut buf = [0u8; 64];
+    let ret = show_or_partial(&mut buf, format_args!("Test {}", 42));
+
+    assert_eq!(ret, Ok("Test 42"));
+}
+
+#[test]
+fn test_show_or_partial_to_long() {
+    let mut buf = [0u8; 8];
+    let ret = show_or_partial(&mut buf, format_args!("Too long string"));
+
+    assert_eq!(ret, Err("Too long"));
+}
+
+#[test]
+fn test_show_or_partial_char_boundary() {
+    let mut buf = [0u8; 4];
+    let ret = show_or_partial(&mut buf, format_args!("abcé"));
+
+    assert_eq!(ret, Err("abc"));
+}
+
+#[test]
+fn test_write_bool() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_bool(&mut w, true, "up", "down").unwrap();
+    write_on_off(&mut w, false).unwrap();
+    write_yes_no(&mut w, true).unwrap();
+
+    assert_eq!(w.as_str(), Some("upoffyes"));
+}
+
+#[test]
+fn test_write_bool_to_long() {
+    let mut buf = [0u8; 2];
+    let mut w = WriteTo::new(&mut buf);
+    let res = write_on_off(&mut w, false);
+
+    assert_eq!(res, Err(core::fmt::Error));
+}
+
+#[test]
+fn test_write_wrapped() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_wrapped("[", format_args!("{}", 5), "]").unwrap();
+
+    assert_eq!(w.as_str(), Some("[5]"));
+}
+
+#[test]
+fn test_write_wrapped_to_long() {
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_wrapped("<", format_args!("a"), ">").unwrap();
+    let res = w.write_wrapped("<", format_args!("b"), ">");
+
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(w.as_str(), Some("<a>"));
+}
+
+#[test]
+fn test_show_chunked() {
+    let expected: [&[u8]; 3] = [b"0123", b"4567", b"89.."];
+    let mut n = 0;
+    show_chunked(format_args!("0{}", 123456789), 4, b'.', |frame| {
+        assert_eq!(frame, expected[n]);
+        n += 1;
+    })
+    .unwrap();
+
+    assert_eq!(n, 3);
+}
+
+#[test]
+fn test_show_chunked_bad_mtu() {
+    let ret = show_chunked(format_args!("a"), 0, 0, |_| {});
+    assert_eq!(ret, Err(fmt::Error));
+
+    let ret = show_chunked(format_args!("a"), MAX_CHUNK_MTU + 1, 0, |_| {});
+    assert_eq!(ret, Err(fmt::Error));
+}
+
+#[test]
+fn test_show_atomic() {
+    let mut buf = [0u8; 64];
+    let s = show_atomic(&mut buf, format_args!("Test {}", 42)).unwrap();
+
+    assert_eq!(s, "Test 42");
+}
+
+#[test]
+fn test_show_atomic_to_long() {
+    let mut buf = [b'x'; 8];
+    let ret = show_atomic(&mut buf, format_args!("Too long string"));
+
+    assert_eq!(Err(fmt::Error), ret);
+    assert_eq!(&buf, b"xxxxxxxx");
+}
+
+#[test]
+fn test_validate() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "Test {}", 42).unwrap();
+
+    assert_eq!(w.validate(), Ok("Test 42"));
+}
+
+#[test]
+fn test_validate_split_char() {
+    use fmt::Write;
+    let mut buf = [0u8; 2];
+    let mut w = WriteTo::new(&mut buf);
+    let res = w.write_str("aé");
+
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(w.validate(), Err(1));
+}
+
+#[test]
+fn test_chunks_utf8() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "aéb€c").unwrap();
+    let mut chunks = w.chunks_utf8(3);
+
+    assert_eq!(chunks.next(), Some("aé"));
+    assert_eq!(chunks.next(), Some("b"));
+    assert_eq!(chunks.next(), Some("€"));
+    assert_eq!(chunks.next(), Some("c"));
+    assert_eq!(chunks.next(), None);
+}
+
+#[test]
+fn test_chunks_utf8_wide_char() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "€a").unwrap();
+    let mut chunks = w.chunks_utf8(1);
+
+    assert_eq!(chunks.next(), Some("€"));
+    assert_eq!(chunks.next(), Some("a"));
+    assert_eq!(chunks.next(), None);
+}
+
+#[test]
+fn test_line_prefix() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = LinePrefixWriter::new(&mut buf, "> ");
+    write!(&mut w, "a\nb").unwrap();
+
+    assert_eq!(w.as_str(), Some("> a\n> b"));
+}
+
+#[test]
+fn test_line_prefix_across_writes() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = LinePrefixWriter::new(&mut buf, "> ");
+    w.write_str("a").unwrap();
+    w.write_str("b\n").unwrap();
+    w.write_str("\nc").unwrap();
+
+    assert_eq!(w.as_str(), Some("> ab\n> \n> c"));
+}
+
+#[test]
+fn test_write_hex_width() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_hex_width(&mut w, 0, 8, false, false).unwrap();
+    assert_eq!(w.as_str(), Some("00000000"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_hex_width(&mut w, 0xdead_beef, 4, false, true).unwrap();
+    assert_eq!(w.as_str(), Some("0xdeadbeef"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_hex_width(&mut w, 0x1f, 4, true, true).unwrap();
+    assert_eq!(w.as_str(), Some("0X001F"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_hex_width(&mut w, 0xab, 2, true, false).unwrap();
+    assert_eq!(w.as_str(), Some("AB"));
+}
+
+#[test]
+fn test_reset() {
+    use fmt::Write;
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::new(&mut buf);
+    let res = write!(&mut w, "Tooo long string");
+    assert_eq!(res, Err(core::fmt::Error));
+
+    w.reset();
+    write!(&mut w, "ok").unwrap();
+
+    assert_eq!(w.as_str(), Some("ok"));
+}
+
+#[test]
+fn test_high_water() {
+    use fmt::Write;
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "abcd").unwrap();
+    w.reset();
+    write!(&mut w, "ab").unwrap();
+    assert_eq!(w.high_water(), 4);
+
+    w.reset();
+    let res = write!(&mut w, "Tooo long string");
+    assert_eq!(res, Err(core::fmt::Error));
+    w.reset();
+
+    assert_eq!(w.high_water(), 16);
+}
+
+#[test]
+fn test_last_required() {
+    use fmt::Write;
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "ab{}", 1).unwrap();
+    assert_eq!(w.last_required(), 3);
+
+    let res = write!(&mut w, "Tooo long {}", 1234);
+    assert_eq!(res, Err(core::fmt::Error));
+
+    assert_eq!(w.last_required(), 14);
+}
+
+#[test]
+fn test_write_debug_list() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_debug_list(&[1, 2, 3]).unwrap();
+    assert_eq!(w.as_str(), Some("[1, 2, 3]"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_debug_list::<u8>(&[]).unwrap();
+    assert_eq!(w.as_str(), Some("[]"));
+}
+
+#[test]
+fn test_write_debug_list_to_long() {
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    let res = w.write_debug_list(&[1, 2, 3]);
+
+    assert_eq!(res, Err(core::fmt::Error));
+}
+
+#[test]
+fn test_finish_show() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "Test ").unwrap();
+    let s = finish_show(w, format_args!("{}", 42)).unwrap();
+
+    assert_eq!(s, "Test 42");
+}
+
+#[test]
+fn test_finish_show_to_long() {
+    let mut buf = [0u8; 8];
+    let w = WriteTo::new(&mut buf);
+    let ret = finish_show(w, format_args!("Too long string"));
+
+    assert_eq!(Err(fmt::Error), ret);
+}
+
+#[test]
+fn test_show_owned() {
+    let s = show_owned::<64>(format_args!("Test String {}: {}", "foo", 42)).unwrap();
+
+    assert_eq!(s.as_str(), "Test String foo: 42");
+    assert_eq!(s.len(), 19);
+    assert!(!s.is_empty());
+}
+
+#[test]
+fn test_show_owned_to_long() {
+    let ret = show_owned::<8>(format_args!("Too long string"));
+
+    assert!(ret.is_err());
+}
+
+#[test]
+fn test_array_string_write() {
+    use fmt::Write;
+    let mut s = ArrayString::<4>::new();
+    s.write_str("ab").unwrap();
+    let res = s.write_str("cde");
+
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(s.as_str(), "ab");
+}
+
+#[test]
+fn test_scoped() {
+    use fmt::Write;
+    let mut buf = [0u8; 16];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "[").unwrap();
+    let res = w.scoped(4, |s| write!(s, "{}", 123456));
+    assert_eq!(res, Err(core::fmt::Error));
+    write!(&mut w, "] ok").unwrap();
+
+    assert_eq!(w.as_str(), Some("[1234] ok"));
+}
+
+#[test]
+fn test_scoped_fits() {
+    use fmt::Write;
+    let mut buf = [0u8; 16];
+    let mut w = WriteTo::new(&mut buf);
+    let res = w.scoped(4, |s| write!(s, "ab"));
+    assert_eq!(res, Ok(()));
+    write!(&mut w, "cd").unwrap();
+
+    assert_eq!(w.as_str(), Some("abcd"));
+}
+
+#[test]
+fn test_write_duration_ms() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_duration_ms(&mut w, 0).unwrap();
+    assert_eq!(w.as_str(), Some("00:00:00.000"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_duration_ms(&mut w, 3_723_004).unwrap();
+    assert_eq!(w.as_str(), Some("01:02:03.004"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_duration_ms(&mut w, 512 * 3_600_000 + 59_999).unwrap();
+    assert_eq!(w.as_str(), Some("512:00:59.999"));
+}
+
+#[test]
+fn test_write_duration_hms() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_duration_hms(&mut w, 3_723_999).unwrap();
+
+    assert_eq!(w.as_str(), Some("01:02:03"));
+}
+
+#[test]
+fn test_line_buffered() {
+    use fmt::Write;
+    let expected = ["a", "b", "c"];
+    let mut n = 0;
+    let mut buf = [0u8; 8];
+    let mut w = LineBufferedWriter::new(&mut buf, |line| {
+        assert_eq!(line, expected[n]);
+        n += 1;
+    });
+    write!(&mut w, "a\nb\nc").unwrap();
+    w.flush();
+    w.flush();
+
+    assert_eq!(n, 3);
+}
+
+#[test]
+fn test_line_buffered_to_long() {
+    use fmt::Write;
+    let mut buf = [0u8; 4];
+    let mut w = LineBufferedWriter::new(&mut buf, |_| {});
+    let res = w.write_str("abcdef\n");
+
+    assert_eq!(res, Err(core::fmt::Error));
+}
+
+#[test]
+fn test_line_buffered_recovers_after_long_line() {
+    use fmt::Write;
+    let expected = ["abcd", "x", "y"];
+    let mut n = 0;
+    let mut buf = [0u8; 4];
+    let mut w = LineBufferedWriter::new(&mut buf, |line| {
+        assert_eq!(line, expected[n]);
+        n += 1;
+    });
+
+    assert!(w.write_str("abcdef\n").is_err());
+    assert!(w.write_str("x\n").is_ok());
+    assert!(w.write_str("y\n").is_ok());
+    assert_eq!(n, 3);
+}
+
+#[test]
+fn test_split_written() {
+    use fmt::Write;
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "hdr:").unwrap();
+    let (head, tail) = w.split_written();
+    tail[..2].copy_from_slice(&head.as_bytes()[..2]);
+
+    assert_eq!(head, "hdr:");
+    assert_eq!(tail.len(), 4);
+    assert_eq!(&buf[4..6], b"hd");
+}
+
+#[test]
+fn test_write_byte_literal() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_byte_literal(&[0x00, 0x41]).unwrap();
+    assert_eq!(w.as_str(), Some("b\"\\x00A\""));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_byte_literal(b"\"\\\n\xff").unwrap();
+    assert_eq!(w.as_str(), Some("b\"\\\"\\\\\\x0a\\xff\""));
+}
+
+#[test]
+fn test_fill_percent() {
+    use fmt::Write;
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::new(&mut buf);
+    assert_eq!(w.fill_percent(), 0);
+
+    write!(&mut w, "abcd").unwrap();
+    assert_eq!(w.fill_percent(), 50);
+
+    let res = write!(&mut w, "efghi");
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(w.fill_percent(), 100);
+}
+
+#[test]
+#[should_panic(expected = "format_no_std: buffer overflow (need 16, cap 8)")]
+fn test_panicking() {
+    use fmt::Write;
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::panicking(&mut buf);
+    let _ = write!(&mut w, "Tooo long string");
+}
+
+#[test]
+fn test_panicking_fits() {
+    use fmt::Write;
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::panicking(&mut buf);
+    write!(&mut w, "12345678").unwrap();
+
+    assert_eq!(w.as_str(), Some("12345678"));
+}
+
+#[test]
+#[should_panic(expected = "format_no_std: buffer overflow (need 2, cap 1)")]
+fn test_panicking_framed() {
+    let mut buf = [0u8; 1];
+    let mut w = WriteTo::panicking(&mut buf);
+    let _ = w.write_framed(format_args!(""));
+}
+
+#[test]
+#[should_panic(expected = "format_no_std: buffer overflow (need 5, cap 4)")]
+fn test_panicking_atomic() {
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::panicking(&mut buf);
+    let _ = w.write_str_atomic("abcde");
+}
+
+#[test]
+#[should_panic(expected = "format_no_std: buffer overflow (need 3, cap 2)")]
+fn test_panicking_scoped() {
+    use fmt::Write;
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::panicking(&mut buf);
+    w.scoped(2, |w| {
+        let _ = w.write_str("abc");
+    });
+}
+
+#[test]
+fn test_write_join() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_join(&mut w, &[1, 2, 3], ", ").unwrap();
+    assert_eq!(w.as_str(), Some("1, 2, 3"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_join(&mut w, &["a"], ", ").unwrap();
+    assert_eq!(w.as_str(), Some("a"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_join::<u8>(&mut w, &[], ", ").unwrap();
+    assert_eq!(w.as_str(), Some(""));
+}
+
+#[test]
+fn test_write_join_to_long() {
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    let res = write_join(&mut w, &[1, 2, 3], ", ");
+
+    assert_eq!(res, Err(core::fmt::Error));
+}
+
+#[test]
+fn test_replace_byte() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "a/b/c").unwrap();
+    w.replace_byte(b'/', b'\\');
+    w.replace_byte(b'a', 0xff);
+
+    assert_eq!(w.as_str(), Some("a\\b\\c"));
+}
+
+#[test]
+fn test_rev_writer() {
+    let mut buf = [0u8; 8];
+    let mut w = RevWriter::new(&mut buf);
+    w.prepend_str("c").unwrap();
+    w.prepend_str("b").unwrap();
+    w.prepend_str("a").unwrap();
+
+    assert_eq!(w.as_str(), Some("abc"));
+}
+
+#[test]
+fn test_rev_writer_to_long() {
+    let mut buf = [0u8; 4];
+    let mut w = RevWriter::new(&mut buf);
+    w.prepend_str("cd").unwrap();
+    let res = w.prepend_str("abc");
+
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(w.as_str(), Some("cd"));
+}
+
+#[test]
+fn test_at_capacity() {
+    use fmt::Write;
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "abc").unwrap();
+    assert!(!w.at_capacity());
+
+    write!(&mut w, "d").unwrap();
+    assert!(w.at_capacity());
+
+    let res = write!(&mut w, "e");
+    assert_eq!(res, Err(core::fmt::Error));
+    assert!(!w.at_capacity());
+}
+
+#[test]
+#[cfg(feature = "unicode-width")]
+fn test_show_to_columns() {
+    let mut buf = [0u8; 64];
+    let s = show_to_columns(&mut buf, format_args!("ab{}", "日本語"), 5);
+    assert_eq!(s, "ab日");
+
+    let mut buf = [0u8; 64];
+    let s = show_to_columns(&mut buf, format_args!("e\u{301}tat"), 3);
+    assert_eq!(s, "e\u{301}ta");
+
+    let mut buf = [0u8; 64];
+    let s = show_to_columns(&mut buf, format_args!("short"), 10);
+    assert_eq!(s, "short");
+}
+
+#[test]
+#[cfg(feature = "unicode-width")]
+fn test_show_to_columns_neutral_emoji() {
+    let mut buf = [0u8; 64];
+    let s = show_to_columns(&mut buf, format_args!("\u{1F321}\u{1F321}"), 2);
+
+    assert_eq!(s, "\u{1F321}\u{1F321}");
+}
+
+#[test]
+#[cfg(feature = "unicode-width")]
+fn test_show_to_columns_combining_mark() {
+    let mut buf = [0u8; 64];
+    let s = show_to_columns(&mut buf, format_args!("\u{0E01}\u{0E34}\u{0E02}"), 2);
+
+    assert_eq!(s, "\u{0E01}\u{0E34}\u{0E02}");
+}
+
+#[test]
+#[cfg(feature = "unicode-width")]
+fn test_show_to_columns_small_buf() {
+    let mut buf = [0u8; 4];
+    let s = show_to_columns(&mut buf, format_args!("日本語"), 6);
+
+    assert_eq!(s, "日");
+}
+
+#[test]
+fn test_tab_expand() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = TabExpandWriter::new(&mut buf, 4);
+    write!(&mut w, "a\tb").unwrap();
+
+    assert_eq!(w.as_str(), Some("a   b"));
+}
+
+#[test]
+fn test_tab_expand_across_lines() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = TabExpandWriter::new(&mut buf, 4);
+    w.write_str("abcd\t").unwrap();
+    w.write_str("e\nf\t").unwrap();
+    w.write_str("\tg").unwrap();
+
+    assert_eq!(w.as_str(), Some("abcd    e\nf       g"));
+}
+
+#[test]
+fn test_show_first_fit() {
+    use fmt::Write;
+    let long = |w: &mut WriteTo| write!(w, "temperature: {} C", 21);
+    let short = |w: &mut WriteTo| write!(w, "t:{}", 21);
+
+    let mut buf = [0u8; 8];
+    let s = show_first_fit(&mut buf, &[&long, &short]);
+    assert_eq!(s, Some("t:21"));
+
+    let mut buf = [0u8; 64];
+    let s = show_first_fit(&mut buf, &[&long, &short]);
+    assert_eq!(s, Some("temperature: 21 C"));
+
+    let mut buf = [0u8; 2];
+    let s = show_first_fit(&mut buf, &[&long, &short]);
+    assert_eq!(s, None);
+}
+
+#[test]
+fn test_trim_end() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "{:8}", "value").unwrap();
+    w.trim_end();
+
+    assert_eq!(w.as_str(), Some("value"));
+}
+
+#[test]
+fn test_trim_end_all_whitespace() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, " \t\r\n").unwrap();
+    w.trim_end();
+
+    assert_eq!(w.as_str(), Some(""));
+}
+
+#[test]
+fn test_show_t() {
+    let mut buf = [0u8; 64];
+    assert_eq!(show_t(&mut buf, 0u8), Ok("0"));
+    assert_eq!(show_t(&mut buf, 42u32), Ok("42"));
+    assert_eq!(show_t(&mut buf, -17i16), Ok("-17"));
+    assert_eq!(show_t(&mut buf, u32::MAX), Ok("4294967295"));
+    assert_eq!(show_t(&mut buf, i32::MIN), Ok("-2147483648"));
+    assert_eq!(show_t(&mut buf, i64::MIN), Ok("-9223372036854775808"));
+    assert_eq!(
+        show_t(&mut buf, u128::MAX),
+        Ok("340282366920938463463374607431768211455")
+    );
+    assert_eq!(show_t(&mut buf, true), Ok("true"));
+    assert_eq!(show_t(&mut buf, 'é'), Ok("é"));
+    assert_eq!(show_t(&mut buf, "foo"), Ok("foo"));
+}
+
+#[test]
+fn test_show_t_to_long() {
+    let mut buf = [0u8; 2];
+
+    assert_eq!(show_t(&mut buf, 123u32), Err(fmt::Error));
+}
+
+#[test]
+fn test_segments() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    assert_eq!(w.segments(), 0);
+
+    let (a, b) = (1, 2);
+    write!(&mut w, "a={} b={}", a, b).unwrap();
+    assert!(w.segments() > 1);
+
+    w.reset();
+    assert_eq!(w.segments(), 0);
+}
+
+#[test]
+fn test_write_duration() {
+    use core::time::Duration;
+    let cases = [
+        (Duration::from_secs(90), "1m30s"),
+        (Duration::from_millis(250), "250ms"),
+        (Duration::from_micros(15), "15us"),
+        (Duration::from_secs(0), "0s"),
+        (Duration::from_secs(3600), "1h"),
+        (Duration::from_millis(7_384_500), "2h3m4s"),
+        (Duration::from_millis(1500), "1s500ms"),
+        (Duration::from_millis(1999), "1s999ms"),
+        (Duration::from_millis(61_500), "1m1s"),
+        (Duration::from_nanos(500), "500ns"),
+    ];
+
+    for &(d, expected) in &cases {
+        let mut buf = [0u8; 64];
+        let mut w = WriteTo::new(&mut buf);
+        w.write_duration(d).unwrap();
+        assert_eq!(w.as_str(), Some(expected));
+    }
+}
+
+#[test]
+fn test_written_len() {
+    use fmt::Write;
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::new(&mut buf);
+    assert_eq!(w.written_len(), 0);
+    assert!(w.is_written_empty());
+
+    write!(&mut w, "abc").unwrap();
+    assert_eq!(w.written_len(), 3);
+    assert!(!w.is_written_empty());
+
+    let res = write!(&mut w, "Tooo long string");
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(w.written_len(), 8);
+    assert_eq!(w.len(), None);
+}
+
+#[test]
+fn test_new_checked() {
+    let mut buf = [0u8; 4];
+    assert!(WriteTo::new_checked(&mut buf, 8).is_err());
+
+    let mut buf = [0u8; 16];
+    assert!(WriteTo::new_checked(&mut buf, 8).is_ok());
+}
+
+#[test]
+fn test_show_spill() {
+    let mut primary = [0u8; 8];
+    let mut fallback = [0u8; 32];
+    let s = show_spill(&mut primary, &mut fallback, format_args!("short")).unwrap();
+    assert_eq!(s, "short");
+    assert_eq!(fallback[0], 0);
+
+    let mut primary = [0u8; 8];
+    let mut fallback = [0u8; 32];
+    let s = show_spill(&mut primary, &mut fallback, format_args!("Too long string")).unwrap();
+    assert_eq!(s, "Too long string");
+    assert_eq!(&fallback[..3], b"Too");
+}
+
+#[test]
+fn test_show_spill_to_long() {
+    let mut primary = [0u8; 4];
+    let mut fallback = [0u8; 8];
+    let ret = show_spill(&mut primary, &mut fallback, format_args!("Too long string"));
+
+    assert_eq!(Err(fmt::Error), ret);
+}
+
+#[test]
+fn test_write_escaped() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_escaped(&mut w, "a\tb \"q\" \\ \x01é\n").unwrap();
+
+    assert_eq!(w.as_str(), Some("a\\tb \\\"q\\\" \\\\ \\x01é\\n"));
+}
+
+#[test]
+fn test_write_escaped_to_long() {
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    let res = write_escaped(&mut w, "abc\n");
+
+    assert_eq!(res, Err(core::fmt::Error));
+}
+
+#[test]
+fn test_recording_writer() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = RecordingWriter::new(&mut buf);
+    let a = w.mark();
+    write!(&mut w, "id={}", 7).unwrap();
+    let b = w.mark();
+    write!(&mut w, ";").unwrap();
+    let c = w.mark();
+    let name = "foo";
+    write!(&mut w, "name={}", name).unwrap();
+    let d = w.mark();
+    let s = w.as_str().unwrap();
+
+    assert_eq!(&s[a..b], "id=7");
+    assert_eq!(&s[c..d], "name=foo");
+}
+
+#[test]
+fn test_with_bom() {
+    use fmt::Write;
+    let mut buf = [0u8; 16];
+    let mut w = WriteTo::with_bom(&mut buf);
+    assert_eq!(w.segments(), 0);
+    write!(&mut w, "hi").unwrap();
+
+    assert_eq!(w.len(), Some(5));
+    assert_eq!(w.as_str(), Some("\u{feff}hi"));
+    assert_eq!(&buf[..3], b"\xef\xbb\xbf");
+}
+
+#[test]
+fn test_with_bom_to_long() {
+    let mut buf = [0u8; 2];
+    let w = WriteTo::with_bom(&mut buf);
+
+    assert_eq!(w.len(), None);
+}
+
+#[test]
+fn test_write_hex_grouped() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_hex_grouped(&[0xde, 0xad, 0xbe, 0xef], 2, ' ', true)
+        .unwrap();
+    assert_eq!(w.as_str(), Some("DEAD BEEF"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_hex_grouped(&[0x01, 0xab, 0xff], 1, ':', false)
+        .unwrap();
+    assert_eq!(w.as_str(), Some("01:ab:ff"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_hex_grouped(&[0x01, 0xab], 0, ' ', false).unwrap();
+    assert_eq!(w.as_str(), Some("01ab"));
+}
+
+#[test]
+fn test_write_hex_grouped_to_long() {
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    let res = w.write_hex_grouped(&[0xde, 0xad, 0xbe], 2, ' ', true);
+
+    assert_eq!(res, Err(core::fmt::Error));
+}
+
+#[test]
+fn test_write_c_string() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_c_string("say \"hi\"\n\x01é").unwrap();
+
+    assert_eq!(w.as_str(), Some("\"say \\\"hi\\\"\\n\\001\\303\\251\""));
+}
+
+#[test]
+fn test_show_detailed() {
+    let mut buf = [0u8; 64];
+    let (s, info) = show_detailed(&mut buf, format_args!("Test {}", 42));
+
+    assert_eq!(s, Some("Test 42"));
+    assert_eq!(
+        info,
+        TruncationInfo {
+            truncated: false,
+            kept: 7,
+            dropped: 0
+        }
+    );
+}
+
+#[test]
+fn test_show_detailed_to_long() {
+    let mut buf = [0u8; 8];
+    let (s, info) = show_detailed(&mut buf, format_args!("Too long {}", "strïng"));
+
+    assert_eq!(s, Some("Too long"));
+    assert_eq!(
+        info,
+        TruncationInfo {
+            truncated: true,
+            kept: 8,
+            dropped: 8
+        }
+    );
+}
+
+#[test]
+fn test_show_detailed_char_boundary() {
+    let mut buf = [0u8; 4];
+    let (s, info) = show_detailed(&mut buf, format_args!("abcé"));
+
+    assert_eq!(s, Some("abc"));
+    assert_eq!(info.kept, 3);
+    assert_eq!(info.dropped, 2);
+}
+
+#[test]
+fn test_show_detailed_formats_once() {
+    use core::cell::Cell;
+    struct Counted<'c>(&'c Cell<usize>);
+    impl fmt::Display for Counted<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.0.set(self.0.get() + 1);
+            f.write_str("abcdef")
+        }
+    }
+
+    let calls = Cell::new(0);
+    let mut buf = [0u8; 4];
+    let (s, info) = show_detailed(&mut buf, format_args!("{}!", Counted(&calls)));
+
+    assert_eq!(s, Some("abcd"));
+    assert_eq!(info.dropped, 3);
+    assert_eq!(calls.get(), 1);
+}
+
+#[test]
+fn test_cformat() {
+    let mut buf = [0u8; 16];
+    let (a, b) = (1, 2);
+    let s = cformat!(buf, "a={}, b={}", a, b).unwrap();
+    assert_eq!(s, "a=1, b=2");
+
+    let mut buf = [0u8; 8];
+    let s = cformat!(buf, "{{{x}}}", x = a).unwrap();
+    assert_eq!(s, "{1}");
+
+    let mut buf = [0u8; 4];
+    let ret = cformat!(buf, "{}", 123456);
+    assert_eq!(ret, Err(fmt::Error));
+}
+
+#[test]
+fn test_literal_len() {
+    assert_eq!(literal_len(""), 0);
+    assert_eq!(literal_len("abc"), 3);
+    assert_eq!(literal_len("a={}, b={:>4}"), 6);
+    assert_eq!(literal_len("{{}}{x}"), 2);
+    assert_eq!(literal_len("é{}"), 2);
+}
+
+#[test]
+fn test_can_fit() {
+    use fmt::Write;
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "ab").unwrap();
+    assert!(w.can_fit(2));
+    assert!(!w.can_fit(3));
+
+    let res = write!(&mut w, "cde");
+    assert_eq!(res, Err(core::fmt::Error));
+    assert!(!w.can_fit(0));
+}
+
+#[test]
+fn test_write_str_atomic() {
+    let mut buf = [b'x'; 4];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_str_atomic("ab").unwrap();
+    let res = w.write_str_atomic("cde");
+
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(w.len(), Some(2));
+    assert_eq!(&buf, b"abxx");
+}
+
+#[test]
+fn test_show_log() {
+    let mut buf = [0u8; 64];
+    let s = show_log(&mut buf, 3_723_004, "INFO", format_args!("boot {}", "ok")).unwrap();
+    assert_eq!(s, "[01:02:03.004] INFO boot ok\n");
+
+    let mut buf = [0u8; 64];
+    let s = show_log(&mut buf, 0, "WARN", format_args!("")).unwrap();
+    assert_eq!(s, "[00:00:00.000] WARN\n");
+
+    let mut buf = [0u8; 64];
+    let s = show_log(&mut buf, u64::MAX, "E", format_args!("x")).unwrap();
+    assert_eq!(s, "[5124095576030:25:51.615] E x\n");
+}
+
+#[test]
+fn test_show_log_to_long() {
+    let mut buf = [0u8; 16];
+    let ret = show_log(&mut buf, 0, "INFO", format_args!("message"));
+
+    assert_eq!(Err(fmt::Error), ret);
+}
+
+#[test]
+fn test_display_width() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = DisplayWidthWriter::new(&mut buf);
+    write!(&mut w, "\x1bA\x1bBé\r\n").unwrap();
+
+    assert_eq!(w.visible_len(), 3);
+    assert_eq!(w.as_str(), Some("\x1bA\x1bBé\r\n"));
+}
+
+#[test]
+fn test_display_width_csi() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = DisplayWidthWriter::new(&mut buf);
+    write!(&mut w, "\x1b[31mhi\x1b[0m").unwrap();
+
+    assert_eq!(w.visible_len(), 9);
+}
+
+#[test]
+fn test_display_width_partial_write() {
+    use fmt::Write;
+    let mut buf = [0u8; 4];
+    let mut w = DisplayWidthWriter::new(&mut buf);
+    w.write_str("ab").unwrap();
+    assert!(w.write_str("cdef").is_err());
+
+    assert_eq!(w.visible_len(), 4);
+}
+
+#[test]
+fn test_display_width_partial_char() {
+    use fmt::Write;
+    let mut buf = [0u8; 4];
+    let mut w = DisplayWidthWriter::new(&mut buf);
+    w.write_str("ab").unwrap();
+    assert!(w.write_str("cé").is_err());
+
+    assert_eq!(w.visible_len(), 3);
+}
+
+#[test]
+fn test_set_buf() {
+    use fmt::Write;
+    let mut buf1 = [0u8; 8];
+    let mut buf2 = [0u8; 8];
+    let mut w = WriteTo::panicking(&mut buf1);
+    write!(&mut w, "first").unwrap();
+    w.set_buf(&mut buf2);
+    write!(&mut w, "second").unwrap();
+
+    assert_eq!(w.high_water(), 6);
+    assert_eq!(w.as_str(), Some("second"));
+    assert_eq!(&buf1[..5], b"first");
+}
+
+#[test]
+fn test_write_from_table() {
+    let table = ["idle", "busy", "error"];
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_from_table(1, &table, "?").unwrap();
+    w.write_from_table(9, &table, "?").unwrap();
+
+    assert_eq!(w.as_str(), Some("busy?"));
+}
+
+#[test]
+fn test_field_writer() {
+    use fmt::Write;
+    let mut buf = [0u8; 10];
+    let mut w = FieldWriter::new(&mut buf);
+    let (id, temp) = (7, 21);
+    let _ = write!(&mut w, "id={};", id);
+    w.field_break();
+    let _ = write!(&mut w, "temp={};", temp);
+    w.field_break();
+    let _ = write!(&mut w, "ok");
+    w.field_break();
+
+    assert_eq!(w.fields_written(), 1);
+}
+
+#[test]
+fn test_field_writer_fits() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = FieldWriter::new(&mut buf);
+    write!(&mut w, "a").unwrap();
+    w.field_break();
+    write!(&mut w, "b").unwrap();
+    w.field_break();
+
+    assert_eq!(w.fields_written(), 2);
+    assert_eq!(w.as_str(), Some("ab"));
+}
+
+#[test]
+fn test_split_written_mut() {
+    use fmt::Write;
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "ab").unwrap();
+    let (head, tail) = w.split_written_mut();
+    head[0] = b'A';
+    tail[..2].copy_from_slice(b"cd");
+    w.advance(2);
+
+    assert_eq!(w.as_str(), Some("Abcd"));
+}
+
+#[test]
+fn test_advance_to_long() {
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    w.advance(5);
+
+    assert_eq!(w.len(), None);
+}
+
+#[test]
+fn test_write_escaped_bytes() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_escaped_bytes(&[b'a', b'\n', 0x00]).unwrap();
+    assert_eq!(w.as_str(), Some("a\\n\\x00"));
+
+    let data = [b'\'', b'"', b'\\', b'\t', 0x7f, 0xff];
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_escaped_bytes(&data).unwrap();
+    let mut expected = [0u8; 64];
+    let mut e = WriteTo::new(&mut expected);
+    for &b in &data {
+        for c in core::ascii::escape_default(b) {
+            fmt::Write::write_char(&mut e, char::from(c)).unwrap();
+        }
+    }
+    assert_eq!(w.as_str(), e.as_str());
+}
+
+#[test]
+fn test_show_length_prefixed() {
+    let mut buf = [0u8; 16];
+    let frame = show_length_prefixed(&mut buf, 2, true, format_args!("hi {}", 7)).unwrap();
+    assert_eq!(frame, b"\x04\x00hi 7");
+
+    let mut buf = [0u8; 16];
+    let frame = show_length_prefixed(&mut buf, 3, false, format_args!("abc")).unwrap();
+    assert_eq!(frame, b"\x00\x00\x03abc");
+}
+
+#[test]
+fn test_show_length_prefixed_errors() {
+    let mut buf = [0u8; 4];
+    let ret = show_length_prefixed(&mut buf, 2, true, format_args!("abc"));
+    assert_eq!(ret, Err(fmt::Error));
+
+    let mut buf = [0u8; 300];
+    let ret = show_length_prefixed(&mut buf, 1, true, format_args!("{:256}", ""));
+    assert_eq!(ret, Err(fmt::Error));
+
+    let mut buf = [0u8; 16];
+    let ret = show_length_prefixed(&mut buf, 0, true, format_args!("a"));
+    assert_eq!(ret, Err(fmt::Error));
+}
+
+#[test]
+fn test_write_identifier() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_identifier(&mut w, "sensor-1.temp_é").unwrap();
+    assert_eq!(w.as_str(), Some("sensor_1_temp__"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_identifier(&mut w, "3d").unwrap();
+    assert_eq!(w.as_str(), Some("_3d"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    write_identifier(&mut w, "").unwrap();
+    assert_eq!(w.as_str(), Some("_"));
+}
+
+#[test]
+fn test_append_from() {
+    use fmt::Write;
+    let mut buf1 = [0u8; 8];
+    let mut a = WriteTo::new(&mut buf1);
+    write!(&mut a, "ab").unwrap();
+    let mut buf2 = [0u8; 8];
+    let mut b = WriteTo::new(&mut buf2);
+    write!(&mut b, "{}", 12).unwrap();
+
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::new(&mut buf);
+    w.append_from(&a).unwrap();
+    w.append_from(&b).unwrap();
+
+    assert_eq!(w.as_str(), Some("ab12"));
+}
+
+#[test]
+fn test_append_from_overflowed() {
+    use fmt::Write;
+    let mut buf1 = [0u8; 2];
+    let mut a = WriteTo::new(&mut buf1);
+    let res = write!(&mut a, "abc");
+    assert_eq!(res, Err(core::fmt::Error));
+
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::new(&mut buf);
+
+    assert_eq!(w.append_from(&a), Err(core::fmt::Error));
+    assert_eq!(w.len(), Some(0));
+}
+
+#[test]
+fn test_static_writer() {
+    let mut w = StaticWriter::<8>::new();
+    assert_eq!(w.format(format_args!("ab")), Ok("ab"));
+    assert_eq!(w.format(format_args!("{}", 12)), Ok("ab12"));
+
+    let ret = w.format(format_args!("Too long"));
+    assert_eq!(ret, Err(fmt::Error));
+    assert_eq!(w.as_str(), Some("ab12"));
+
+    w.reset();
+    assert_eq!(w.format(format_args!("new")), Ok("new"));
+}
+
+#[test]
+fn test_write_signed() {
+    let cases = [
+        (5, true, "+5"),
+        (-5, true, "-5"),
+        (5, false, "5"),
+        (0, true, "+0"),
+        (i64::MIN, false, "-9223372036854775808"),
+    ];
+
+    for &(value, force_plus, expected) in &cases {
+        let mut buf = [0u8; 64];
+        let mut w = WriteTo::new(&mut buf);
+        w.write_signed(value, force_plus).unwrap();
+        assert_eq!(w.as_str(), Some(expected));
+    }
+}
+
+#[test]
+fn test_remaining() {
+    use fmt::Write;
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "ab").unwrap();
+    assert_eq!(w.remaining(), 2);
+
+    let res = write!(&mut w, "cde");
+    assert_eq!(res, Err(core::fmt::Error));
+    assert_eq!(w.remaining(), 0);
+}
+
+#[test]
+fn test_write_if_fits() {
+    let mut buf = [0u8; 8];
+    let mut w = WriteTo::new(&mut buf);
+    let (temp, unit) = (21, 'C');
+    assert!(w.write_if_fits(format_args!("t={}", temp)));
+    assert!(!w.write_if_fits(format_args!(" unit={}", unit)));
+    assert!(w.write_if_fits(format_args!("{}", unit)));
+
+    assert_eq!(w.as_str(), Some("t=21C"));
+}
+
+#[test]
+fn test_max_line() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = MaxLineWriter::new(&mut buf, 10);
+    write!(&mut w, "aaaaaaaaaabbbbbbbbbbcccccccccc").unwrap();
+
+    assert_eq!(w.as_str(), Some("aaaaaaaaaa\nbbbbbbbbbb\ncccccccccc"));
+}
+
+#[test]
+fn test_max_line_across_writes() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = MaxLineWriter::new(&mut buf, 3);
+    w.write_str("ab").unwrap();
+    w.write_str("cdé\nfg").unwrap();
+    w.write_str("hi").unwrap();
+
+    assert_eq!(w.as_str(), Some("abc\ndé\nfgh\ni"));
+}
+
+#[test]
+fn test_write_kv_pairs() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_kv_pairs(&[("a", 1), ("b", 2)], '=', ';').unwrap();
+    assert_eq!(w.as_str(), Some("a=1;b=2"));
+
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+    w.write_kv_pairs::<&str, u8>(&[], '=', ';').unwrap();
+    assert_eq!(w.as_str(), Some(""));
+}
+
+#[test]
+fn test_write_kv_pairs_to_long() {
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    let res = w.write_kv_pairs(&[("a", 1), ("b", 2)], '=', ';');
+
+    assert_eq!(res, Err(core::fmt::Error));
+}
+
+#[test]
+fn test_write_fmt_formats_once_on_failure() {
+    use core::cell::Cell;
+    use fmt::Write;
+    struct Counted<'c>(&'c Cell<usize>);
+    impl fmt::Display for Counted<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.0.set(self.0.get() + 1);
+            f.write_str("abcdef")
+        }
+    }
+
+    let calls = Cell::new(0);
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    assert!(write!(&mut w, "{}", Counted(&calls)).is_err());
+    assert_eq!(calls.get(), 1);
+    assert_eq!(w.last_required(), 6);
+
+    calls.set(0);
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    assert!(w
+        .write_kv_pairs(&[("a", Counted(&calls))], '=', ';')
+        .is_err());
+    assert_eq!(calls.get(), 1);
+}
+
+#[test]
+fn test_mapping_writer() {
+    use fmt::Write;
+    let rot13 = |b: u8| match b {
+        b'a'..=b'z' => (b - b'a' + 13) % 26 + b'a',
+        b'A'..=b'Z' => (b - b'A' + 13) % 26 + b'A',
+        _ => b,
+    };
+    let mut buf = [0u8; 64];
+    let mut w = MappingWriter::new(&mut buf, rot13);
+    let name = "World";
+    write!(&mut w, "Hello, {}!", name).unwrap();
+
+    assert_eq!(w.as_str(), Some("Uryyb, Jbeyq!"));
+}
+
+#[test]
+fn test_mapping_writer_invalid_utf8() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = MappingWriter::new(&mut buf, |b| b | 0x80);
+    w.write_str("a").unwrap();
+
+    assert_eq!(w.as_str(), None);
+}
+
+#[test]
+fn test_last_error() {
+    use fmt::Write;
+    struct Failing;
+    impl fmt::Display for Failing {
+        fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+            Err(fmt::Error)
+        }
+    }
+
+    let mut buf = [0u8; 4];
+    let mut w = WriteTo::new(&mut buf);
+    write!(&mut w, "ab").unwrap();
+    assert_eq!(w.last_error(), None);
+
+    let _ = write!(&mut w, "{}", Failing);
+    assert_eq!(w.last_error(), Some(WriteError::Format));
+
+    let _ = write!(&mut w, "cde");
+    let _ = write!(&mut w, "f");
+    assert_eq!(w.last_error(), Some(WriteError::Overflow));
+
+    w.reset();
+    assert_eq!(w.last_error(), None);
+}
+
+#[test]
+fn test_last_error_helpers() {
+    struct Failing;
+    impl fmt::Display for Failing {
+        fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+            Err(fmt::Error)
+        }
+    }
+
+    let mut buf = [0u8; 16];
+    let mut w = WriteTo::new(&mut buf);
+    assert!(w
+        .write_wrapped("<", format_args!("{}", Failing), ">")
+        .is_err());
+    assert_eq!(w.last_error(), Some(WriteError::Format));
+
+    let mut buf = [0u8; 16];
+    let mut w = WriteTo::new(&mut buf);
+    assert!(!w.write_if_fits(format_args!("{}", Failing)));
+    assert_eq!(w.last_error(), Some(WriteError::Format));
+
+    let mut buf = [0u8; 16];
+    let mut w = WriteTo::new(&mut buf);
+    assert!(w.write_f32(1e30, 2).is_err());
+    assert_eq!(w.last_error(), Some(WriteError::Format));
+    assert!(w.write_f32(1.0, 20).is_err());
+    assert_eq!(w.last_error(), Some(WriteError::Format));
+
+    let mut buf = [0u8; 16];
+    let mut w = WriteTo::new(&mut buf);
+    assert!(w.write_framed(format_args!("abcdefghijklmnop")).is_err());
+    assert_eq!(w.last_error(), Some(WriteError::Overflow));
+
+    let mut small = [0u8; 2];
+    let mut other = WriteTo::new(&mut small);
+    let _ = fmt::Write::write_str(&mut other, "abc");
+    let mut buf = [0u8; 16];
+    let mut w = WriteTo::new(&mut buf);
+    assert!(w.append_from(&other).is_err());
+    assert_eq!(w.last_error(), Some(WriteError::Overflow));
+}
+
+#[test]
+fn test_last_error_frame_too_long() {
+    struct Long;
+    impl fmt::Display for Long {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            for _ in 0..256 {
+                f.write_str(core::str::from_utf8(&[b'x'; 256]).unwrap())?;
+            }
+            Ok(())
+        }
+    }
+
+    let mut buf = [0u8; 70_000];
+    let mut w = WriteTo::new(&mut buf);
+    assert!(w.write_framed(format_args!("{}", Long)).is_err());
+
+    assert_eq!(w.last_error(), Some(WriteError::Format));
+    assert_eq!(w.len(), Some(0));
+}
+
+#[test]
+#[allow(clippy::approx_constant)]
+fn test_write_f32() {
+    let cases = [
+        (3.14159, 2, "3.14"),
+        (2.5, 0, "3"),
+        (-1.005, 1, "-1.0"),
+        (0.05, 3, "0.050"),
+        (-0.001, 2, "0.00"),
+        (100.0, 2, "100.00"),
+        (f32::NAN, 2, "NaN"),
+        (f32::NEG_INFINITY, 2, "-inf"),
+    ];
+
+    for &(value, decimals, expected) in &cases {
+        let mut buf = [0u8; 64];
+        let mut w = WriteTo::new(&mut buf);
+        w.write_f32(value, decimals).unwrap();
+        assert_eq!(w.as_str(), Some(expected));
+    }
+}
+
+#[test]
+fn test_write_f32_out_of_range() {
+    let mut buf = [0u8; 64];
+    let mut w = WriteTo::new(&mut buf);
+
+    assert_eq!(w.write_f32(1e30, 2), Err(core::fmt::Error));
+    assert_eq!(w.write_f32(1.0, 20), Err(core::fmt::Error));
+}
+
+#[test]
+fn test_show_items() {
+    let mut buf = [0u8; 64];
+    let (s, n) = show_items(&mut buf, [1, 2, 3].iter(), ", ");
+    assert_eq!(s, "1, 2, 3");
+    assert_eq!(n, 3);
+
+    let mut buf = [0u8; 64];
+    let (s, n) = show_items(&mut buf, core::iter::empty::<u8>(), ", ");
+    assert_eq!(s, "");
+    assert_eq!(n, 0);
+}
+
+#[test]
+fn test_show_items_to_long() {
+    let mut buf = [0u8; 8];
+    let (s, n) = show_items(&mut buf, 100..1000, " ");
+
+    assert_eq!(s, "100 101");
+    assert_eq!(n, 2);
+}
+
+#[test]
+fn test_show_items_formats_once() {
+    use core::cell::Cell;
+    struct Counted<'c>(&'c Cell<usize>, &'static str);
+    impl fmt::Display for Counted<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.0.set(self.0.get() + 1);
+            f.write_str(self.1)
+        }
+    }
+
+    let calls = Cell::new(0);
+    let items = ["aaa", "bbb", "ccc"].iter().map(|s| Counted(&calls, s));
+    let mut buf = [0u8; 5];
+    let (s, n) = show_items(&mut buf, items, ",");
+    assert_eq!((s, n), ("aaa", 1));
+    assert_eq!(calls.get(), 2);
+
+    calls.set(0);
+    let items = [Counted(&calls, "aaa"), Counted(&calls, "bbb")];
+    let mut buf = [0u8; 5];
+    let mut w = WriteTo::new(&mut buf);
+    assert!(write_join(&mut w, &items, ",").is_err());
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn test_filter_writer() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = FilterWriter::new(&mut buf, |b| b != 0x13 && b != 0x11);
+    write!(&mut w, "a\x13b\x11\x13c").unwrap();
+
+    assert_eq!(w.as_str(), Some("abc"));
+}
+
+#[test]
+fn test_filter_writer_whole_chars() {
+    use fmt::Write;
+    let mut buf = [0u8; 64];
+    let mut w = FilterWriter::new(&mut buf, |b| b.is_ascii());
+    write!(&mut w, "aé€b").unwrap();
+
+    assert_eq!(w.as_str(), Some("ab"));
+}
+
+#[test]
+fn test_show_ellipsized() {
+    let mut buf = [0u8; 32];
+    let s = show_ellipsized(&mut buf, format_args!("Short {}", 1));
+    assert_eq!(s, "Short 1");
+
+    let mut buf = [0u8; 8];
+    let s = show_ellipsized(&mut buf, format_args!("Some very long label"));
+    assert_eq!(s, "Some …");
+    assert_eq!(s.len(), 8);
+}
+
+#[test]
+fn test_show_ellipsized_char_boundary() {
+    let mut buf = [0u8; 6];
+    let s = show_ellipsized(&mut buf, format_args!("aéééé"));
+    assert_eq!(s, "aé…");
+
+    let mut buf = [0u8; 6];
+    let s = show_ellipsized_with(&mut buf, format_args!("abcdefgh"), "..");
+    assert_eq!(s, "abcd..");
+
+    let mut buf = [0u8; 2];
+    let s = show_ellipsized(&mut buf, format_args!("abcdefgh"));
+    assert_eq!(s, "");
+}
+
+#[test]
+fn test_show_ellipsized_format_error() {
+    struct Failing;
+    impl fmt::Display for Failing {
+        fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+            Err(fmt::Error)
+        }
+    }
+
+    let mut buf = [0u8; 16];
+    let s = show_ellipsized(&mut buf, format_args!("ab{}", Failing));
+
+    assert_eq!(s, "ab");
 }